@@ -1,5 +1,6 @@
 import { execSync, spawn } from 'child_process';
 import fs from 'fs';
+import os from 'os';
 import path from 'path';
 import { fileURLToPath } from 'url';
 
@@ -68,6 +69,46 @@ if (command === 'tauri' && (commandArgs.includes('build') || commandArgs.include
   }
 }
 
+/**
+ * Kernel platform name ({os}-{arch}), same naming as src-tauri/build.rs detect_platform.
+ * Uses the --target triple when one is given, otherwise the host.
+ */
+function detectKernelPlatform(triple) {
+  if (triple) {
+    const osName = triple.includes('windows') ? 'win'
+      : triple.includes('linux') ? 'linux'
+      : triple.includes('darwin') ? 'macos'
+      : 'unknown';
+    const arch = /x86_64|amd64/.test(triple) ? 'x64'
+      : /aarch64|arm64/.test(triple) ? 'arm64'
+      : /i686|i386/.test(triple) ? 'x86'
+      : triple.includes('armv7') ? 'arm'
+      : 'unknown';
+    return `${osName}-${arch}`;
+  }
+  const osName = { win32: 'win', linux: 'linux', darwin: 'macos' }[process.platform] || 'unknown';
+  const arch = { x64: 'x64', arm64: 'arm64', ia32: 'x86', arm: 'arm' }[process.arch] || 'unknown';
+  return `${osName}-${arch}`;
+}
+
+// Bundle only the kernel zip of the platform being built. Platforms without a
+// prebuilt kernel ship none and offer a download at runtime (VITE_CORE_DOWNLOAD_URL).
+if (command === 'tauri' && commandArgs.includes('build')) {
+  const targetIndex = commandArgs.findIndex((arg) => arg === '--target' || arg === '-t');
+  const platform = detectKernelPlatform(targetIndex >= 0 ? commandArgs[targetIndex + 1] : null);
+  const zipName = `yuHai-core-${platform}.zip`;
+  if (fs.existsSync(path.resolve(__dirname, '../src-tauri/resources', zipName))) {
+    const overlayPath = path.join(os.tmpdir(), `yuHai-tauri-resources-${platform}.json`);
+    fs.writeFileSync(overlayPath, JSON.stringify({ bundle: { resources: [`resources/${zipName}`] } }));
+    // Arguments after `--` belong to cargo
+    const separator = commandArgs.indexOf('--');
+    commandArgs.splice(separator >= 0 ? separator : commandArgs.length, 0, '--config', `"${overlayPath}"`);
+    console.log(`[Kernel Bundle] Bundling resources/${zipName}`);
+  } else {
+    console.warn(`[Kernel Bundle] resources/${zipName} not found, building without a bundled kernel`);
+  }
+}
+
 // Spawn the command
 // shell: true is required for Windows to resolve npm/pnpm commands and shell operators
 const child = spawn(command, commandArgs, {
@@ -34,15 +34,20 @@ fn main() {
         println!("cargo:rustc-env=VITE_CORE_EXECUTABLE={}", exe_name);
     }
     
-    if let Ok(val) = std::env::var("VITE_CORE_INSTALL_DIR") {
-        println!("cargo:rustc-env=VITE_CORE_INSTALL_DIR={}", val);
-    }
-    if let Ok(val) = std::env::var("VITE_CORE_API_URL") {
-        println!("cargo:rustc-env=VITE_CORE_API_URL={}", val);
-    }
-    
-    if let Ok(val) = std::env::var("VITE_CORE_UPDATE_SERVER_URL") {
-        println!("cargo:rustc-env=VITE_CORE_UPDATE_SERVER_URL={}", val);
+    // Every other kernel setting is read with option_env!, forward them all from .env
+    // or the build environment. Empty values in .env mean "use the default".
+    let mut settings: Vec<(String, String)> = std::env::vars()
+        .filter(|(key, value)| {
+            key.starts_with("VITE_CORE_")
+                && key != "VITE_CORE_ZIP_NAME"
+                && key != "VITE_CORE_EXECUTABLE"
+                && !value.trim().is_empty()
+        })
+        .collect();
+    settings.sort();
+    for (key, value) in settings {
+        println!("cargo:rerun-if-env-changed={}", key);
+        println!("cargo:rustc-env={}={}", key, value.trim());
     }
 
     tauri_build::build()
@@ -101,11 +101,15 @@ async fn sync_browser_position<R: tauri::Runtime>(window: &WebviewWindow<R>) {
     };
 
     let client = window.state::<reqwest::Client>();
-    let browser_api_url = format!("{}/api/v1/browser/position", crate::utils::core_api_base());
+    let endpoint = window.state::<crate::core_api::CoreEndpoint>();
+    // A remote kernel's browser isn't on this screen
+    if endpoint.is_remote() {
+        return;
+    }
 
     // We ignore errors here as we don't want to crash or spam logs too much, 
     // but logging debug info is good.
-    match client.post(&browser_api_url)
+    match endpoint.post(&client, "/api/v1/browser/position")
         .json(&payload)
         .send()
         .await 
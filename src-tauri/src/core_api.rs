@@ -0,0 +1,227 @@
+use std::net::{Ipv4Addr, SocketAddr, TcpListener};
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Runtime};
+
+const CORE_HOST: &str = "127.0.0.1";
+const LAUNCH_RECORD_FILE: &str = "launch.json";
+
+// Per-launch shared secret: handed to the kernel in the environment and sent back
+// on every request so other local processes can't drive the kernel
+pub const CORE_TOKEN_ENV: &str = "YUHAI_CORE_TOKEN";
+pub const CORE_TOKEN_HEADER: &str = "X-YuHai-Token";
+
+// Base URL of the running kernel. Starts at the compile-time default and is
+// replaced by `start_core` once a port has been allocated for the process, or
+// by the remote profile when the kernel runs on another machine.
+pub struct CoreEndpoint {
+    base: RwLock<String>,
+    // None for kernels the shell didn't launch
+    token: RwLock<Option<String>>,
+    // TLS client of the remote profile, replaces the managed client while set
+    remote_client: RwLock<Option<reqwest::Client>>,
+}
+
+impl CoreEndpoint {
+    pub fn new() -> Self {
+        Self {
+            base: RwLock::new(crate::utils::core_api_base().trim_end_matches('/').to_string()),
+            token: RwLock::new(None),
+            remote_client: RwLock::new(None),
+        }
+    }
+
+    pub fn is_remote(&self) -> bool {
+        self.remote_client.read().map(|c| c.is_some()).unwrap_or(false)
+    }
+
+    pub fn set_remote(&self, base: String, token: Option<String>, client: reqwest::Client) {
+        self.set_base(base);
+        self.set_token(token);
+        if let Ok(mut c) = self.remote_client.write() {
+            *c = Some(client);
+        }
+    }
+
+    // Back to the local kernel at the compile-time default address
+    pub fn clear_remote(&self) {
+        if let Ok(mut c) = self.remote_client.write() {
+            *c = None;
+        }
+        self.set_token(None);
+        self.set_base(crate::utils::core_api_base());
+    }
+
+    // The client that can reach the current kernel
+    pub fn client(&self, local: &reqwest::Client) -> reqwest::Client {
+        self.remote_client
+            .read()
+            .ok()
+            .and_then(|c| c.clone())
+            .unwrap_or_else(|| local.clone())
+    }
+
+    // Authorized request to `path` on the current kernel
+    pub fn request(&self, local: &reqwest::Client, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        self.authorize(self.client(local).request(method, self.url(path)))
+    }
+
+    pub fn get(&self, local: &reqwest::Client, path: &str) -> reqwest::RequestBuilder {
+        self.request(local, reqwest::Method::GET, path)
+    }
+
+    pub fn post(&self, local: &reqwest::Client, path: &str) -> reqwest::RequestBuilder {
+        self.request(local, reqwest::Method::POST, path)
+    }
+
+    pub fn token(&self) -> Option<String> {
+        self.token.read().ok().and_then(|t| t.clone())
+    }
+
+    pub fn set_token(&self, token: Option<String>) {
+        if let Ok(mut t) = self.token.write() {
+            *t = token;
+        }
+    }
+
+    pub fn authorize(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match self.token() {
+            Some(token) => request.header(CORE_TOKEN_HEADER, token),
+            None => request,
+        }
+    }
+
+    pub fn authorize_blocking(&self, request: reqwest::blocking::RequestBuilder) -> reqwest::blocking::RequestBuilder {
+        match self.token() {
+            Some(token) => request.header(CORE_TOKEN_HEADER, token),
+            None => request,
+        }
+    }
+
+    pub fn base(&self) -> String {
+        self.base
+            .read()
+            .map(|b| b.clone())
+            .unwrap_or_else(|_| crate::utils::core_api_base().to_string())
+    }
+
+    pub fn set_base(&self, base: String) {
+        if let Ok(mut b) = self.base.write() {
+            *b = base.trim_end_matches('/').to_string();
+        }
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base(), path)
+    }
+}
+
+fn default_port() -> Option<u16> {
+    let base = crate::utils::core_api_base();
+    let authority = base.split("://").nth(1).unwrap_or(&base);
+    let host_port = authority.split('/').next().unwrap_or(authority);
+    host_port.rsplit_once(':').and_then(|(_, port)| port.parse().ok())
+}
+
+pub fn port_is_free(port: u16) -> bool {
+    TcpListener::bind(SocketAddr::from((Ipv4Addr::LOCALHOST, port))).is_ok()
+}
+
+// Prefer the configured default port so the dev proxy keeps working,
+// otherwise let the OS hand out a free one.
+pub fn allocate_port() -> std::io::Result<u16> {
+    if let Some(port) = default_port() {
+        if port_is_free(port) {
+            return Ok(port);
+        }
+        log::warn!("Default kernel port {} is in use, allocating a free one", port);
+    }
+    let listener = TcpListener::bind(SocketAddr::from((Ipv4Addr::LOCALHOST, 0)))?;
+    Ok(listener.local_addr()?.port())
+}
+
+pub fn generate_token() -> std::io::Result<String> {
+    let mut bytes = [0u8; 32];
+    getrandom::fill(&mut bytes).map_err(|e| std::io::Error::other(e.to_string()))?;
+    Ok(hex::encode(bytes))
+}
+
+// The kernel we spawned last, kept on disk so a shell that crashed can find the
+// kernel it left behind on the next start and stop it with its token
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LaunchRecord {
+    pub pid: u32,
+    pub port: u16,
+    pub token: String,
+}
+
+fn launch_record_path<R: Runtime>(app: &AppHandle<R>) -> Option<PathBuf> {
+    app.path().app_data_dir().ok().map(|dir| dir.join("core").join(LAUNCH_RECORD_FILE))
+}
+
+pub fn load_launch_record<R: Runtime>(app: &AppHandle<R>) -> Option<LaunchRecord> {
+    let content = std::fs::read_to_string(launch_record_path(app)?).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+// Readable by the current user only, the token grants full control of the kernel
+pub fn save_launch_record<R: Runtime>(app: &AppHandle<R>, record: &LaunchRecord) -> Result<(), String> {
+    use std::io::Write;
+
+    let path = launch_record_path(app).ok_or("App data dir not available")?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let content = serde_json::to_string_pretty(record).map_err(|e| e.to_string())?;
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(&path).map_err(|e| e.to_string())?;
+    file.write_all(content.as_bytes()).map_err(|e| e.to_string())
+}
+
+pub fn clear_launch_record<R: Runtime>(app: &AppHandle<R>) {
+    if let Some(path) = launch_record_path(app) {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+pub fn local_base(port: u16) -> String {
+    format!("http://{}:{}", CORE_HOST, port)
+}
+
+pub fn core_host() -> &'static str {
+    CORE_HOST
+}
+
+#[tauri::command]
+pub fn get_core_api_base(endpoint: tauri::State<'_, CoreEndpoint>) -> Result<String, String> {
+    Ok(endpoint.base())
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CoreAuth {
+    pub header: &'static str,
+    pub token: Option<String>,
+    // Remote kernels are reached through `core_proxy_request` instead
+    pub proxy: bool,
+}
+
+// Lets the frontend's coreRequest authenticate like the shell does
+#[tauri::command]
+pub fn get_core_auth(endpoint: tauri::State<'_, CoreEndpoint>) -> Result<CoreAuth, String> {
+    let proxy = endpoint.is_remote();
+    Ok(CoreAuth {
+        header: CORE_TOKEN_HEADER,
+        token: if proxy { None } else { endpoint.token() },
+        proxy,
+    })
+}
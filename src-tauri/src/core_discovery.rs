@@ -0,0 +1,338 @@
+use std::env;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, UNIX_EPOCH};
+
+use futures_util::stream::{self, StreamExt};
+use regex::Regex;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Manager, Runtime};
+
+const MANIFEST_FILE: &str = "kernels.json";
+// Bumped whenever the way entries are probed changes, so stale entries get re-probed
+const MANIFEST_FORMAT: u32 = 3;
+
+fn get_scan_max_depth() -> usize {
+    option_env!("VITE_CORE_SCAN_MAX_DEPTH")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(4)
+}
+
+fn get_probe_concurrency() -> usize {
+    option_env!("VITE_CORE_PROBE_CONCURRENCY")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(4)
+}
+
+fn get_probe_timeout_ms() -> u64 {
+    option_env!("VITE_CORE_PROBE_TIMEOUT_MS")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(5000)
+}
+
+pub fn kernel_exe_name() -> &'static str {
+    option_env!("VITE_CORE_EXE_NAME")
+        .unwrap_or(if cfg!(target_os = "windows") { "yuHai.exe" } else { "yuHai" })
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestEntry {
+    pub path: PathBuf,
+    pub version: Option<String>,
+    // Only computed when the version had to be probed, so a binary that merely
+    // got a new mtime isn't executed again
+    #[serde(default)]
+    pub sha256: Option<String>,
+    pub size: u64,
+    pub modified_ms: i64,
+}
+
+impl ManifestEntry {
+    pub fn semver(&self) -> Option<Version> {
+        self.version.as_deref().and_then(parse_version)
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct KernelManifest {
+    #[serde(default)]
+    format: u32,
+    #[serde(default)]
+    kernels: Vec<ManifestEntry>,
+}
+
+// Cached inventory of kernel executables, persisted to app_data/core/kernels.json.
+// Binaries are only looked at again when their size or mtime changed, or when no
+// version could be determined for them last time.
+pub struct KernelDiscovery {
+    manifest_path: Option<PathBuf>,
+    manifest: Mutex<KernelManifest>,
+    refresh_lock: tokio::sync::Mutex<()>,
+}
+
+impl KernelDiscovery {
+    pub fn load<R: Runtime>(app: &AppHandle<R>) -> Self {
+        let manifest_path = app.path().app_data_dir().ok().map(|dir| dir.join("core").join(MANIFEST_FILE));
+        let manifest = manifest_path
+            .as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|content| serde_json::from_str::<KernelManifest>(&content).ok())
+            .filter(|m| m.format == MANIFEST_FORMAT)
+            .unwrap_or_default();
+        Self {
+            manifest_path,
+            manifest: Mutex::new(manifest),
+            refresh_lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    fn save(&self, manifest: &KernelManifest) {
+        let Some(path) = &self.manifest_path else {
+            return;
+        };
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        match serde_json::to_string_pretty(manifest) {
+            Ok(content) => {
+                if let Err(e) = std::fs::write(path, content) {
+                    log::warn!("Failed to write kernel manifest: {}", e);
+                }
+            }
+            Err(e) => log::warn!("Failed to serialize kernel manifest: {}", e),
+        }
+    }
+}
+
+// Roots that may contain kernels
+fn scan_roots<R: Runtime>(app: &AppHandle<R>) -> Vec<PathBuf> {
+    let mut roots = Vec::new();
+    if let Ok(dir) = env::var("VITE_CORE_INSTALL_DIR") {
+        if !dir.is_empty() {
+            roots.push(PathBuf::from(dir));
+        }
+    }
+    if let Ok(app_data_dir) = app.path().app_data_dir() {
+        roots.push(app_data_dir.join("core"));
+    }
+    roots
+}
+
+// Walk the roots up to VITE_CORE_SCAN_MAX_DEPTH levels. Symlinked directories are
+// not followed, so link loops can't trap the walk.
+pub fn find_candidates<R: Runtime>(app: &AppHandle<R>) -> Vec<PathBuf> {
+    let exe_name = kernel_exe_name();
+    let max_depth = get_scan_max_depth();
+    let kernels_root = crate::core_install::kernels_dir(app).ok();
+    let mut found = Vec::new();
+
+    for root in scan_roots(app) {
+        let mut stack = vec![(root, 0usize)];
+        while let Some((dir, depth)) = stack.pop() {
+            let exe = dir.join(exe_name);
+            let usable = kernels_root
+                .as_ref()
+                .is_none_or(|root| crate::core_install::is_usable_kernel_dir(root, &dir));
+            if usable && exe.is_file() && !found.contains(&exe) {
+                found.push(exe);
+            }
+            if depth >= max_depth {
+                continue;
+            }
+            let entries = match std::fs::read_dir(&dir) {
+                Ok(entries) => entries,
+                Err(_) => continue,
+            };
+            for entry in entries.flatten() {
+                // `DirEntry::file_type` does not follow symlinks
+                if entry.file_type().is_ok_and(|t| t.is_dir()) && !crate::core_install::is_staging_dir(&entry.path()) {
+                    stack.push((entry.path(), depth + 1));
+                }
+            }
+        }
+    }
+    found
+}
+
+pub fn has_any_kernel<R: Runtime>(app: &AppHandle<R>) -> bool {
+    !find_candidates(app).is_empty()
+}
+
+// Manifest entries whose executable is still present, without probing anything
+pub fn cached<R: Runtime>(app: &AppHandle<R>) -> Vec<ManifestEntry> {
+    let discovery = app.state::<KernelDiscovery>();
+    let manifest = match discovery.manifest.lock() {
+        Ok(m) => m,
+        Err(_) => return Vec::new(),
+    };
+    manifest.kernels.iter().filter(|e| e.path.is_file()).cloned().collect()
+}
+
+fn file_stamp(path: &Path) -> Option<(u64, i64)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0);
+    Some((metadata.len(), modified))
+}
+
+pub fn hash_file(path: &Path) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+// Full semver including pre-release and build metadata, with an optional leading "v"
+pub fn parse_version(raw: &str) -> Option<Version> {
+    let raw = raw.trim();
+    Version::parse(raw.strip_prefix('v').unwrap_or(raw)).ok()
+}
+
+// Run `<kernel> <args>` with a timeout, returning its stdout
+async fn run_kernel(path: &Path, args: &[&str]) -> Option<String> {
+    let mut cmd = tokio::process::Command::new(path);
+    cmd.args(args)
+        .stdin(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .kill_on_drop(true);
+
+    #[cfg(target_os = "windows")]
+    cmd.creation_flags(0x08000000);
+
+    match tokio::time::timeout(Duration::from_millis(get_probe_timeout_ms()), cmd.output()).await {
+        Ok(Ok(output)) => Some(String::from_utf8_lossy(&output.stdout).into_owned()),
+        Ok(Err(e)) => {
+            log::warn!("Failed to run {:?} {}: {}", path, args.join(" "), e);
+            None
+        }
+        Err(_) => {
+            log::warn!("{:?} {} timed out", path, args.join(" "));
+            None
+        }
+    }
+}
+
+// Prefer the structured `version --json` output ({"version": "1.2.0-beta.2+build.5"}),
+// older kernels only print a "Version: x.y.z" line.
+async fn probe_version(path: &Path) -> Option<Version> {
+    if let Some(stdout) = run_kernel(path, &["version", "--json"]).await {
+        let version = serde_json::from_str::<serde_json::Value>(stdout.trim())
+            .ok()
+            .and_then(|json| json.get("version").and_then(|v| v.as_str()).and_then(parse_version));
+        if version.is_some() {
+            return version;
+        }
+    }
+
+    let stdout = run_kernel(path, &["version"]).await?;
+    let re = Regex::new(r"Version:\s*(\S+)").unwrap();
+    re.captures(&stdout)
+        .and_then(|caps| caps.get(1))
+        .and_then(|m| parse_version(m.as_str()))
+}
+
+// Version recorded by the installer in the kernel's `.install-complete` marker
+fn installed_version(path: &Path) -> Option<Version> {
+    let marker = crate::core_install::read_marker(path.parent()?)?;
+    marker.version.as_deref().and_then(parse_version)
+}
+
+// The install marker is trusted first. Otherwise the binary is hashed, and only
+// executed when it isn't the same binary a version was already probed for.
+async fn probe(path: PathBuf, size: u64, modified_ms: i64, previous: Option<ManifestEntry>) -> ManifestEntry {
+    let mut entry = ManifestEntry {
+        path,
+        version: None,
+        sha256: None,
+        size,
+        modified_ms,
+    };
+    if let Some(version) = installed_version(&entry.path) {
+        log::info!("Kernel at {:?} was installed as version {}", entry.path, version);
+        entry.version = Some(version.to_string());
+        return entry;
+    }
+
+    let hash_path = entry.path.clone();
+    entry.sha256 = tauri::async_runtime::spawn_blocking(move || hash_file(&hash_path))
+        .await
+        .ok()
+        .and_then(|r| r.ok());
+    if let Some(previous) = previous.filter(|p| p.version.is_some() && p.sha256.is_some() && p.sha256 == entry.sha256) {
+        entry.version = previous.version;
+        return entry;
+    }
+
+    let version = probe_version(&entry.path).await;
+    log::info!("Probed kernel at {:?}: version {:?}", entry.path, version);
+    entry.version = version.map(|v| v.to_string());
+    entry
+}
+
+// Re-scan the disk, probe new or changed kernels in parallel and persist the manifest
+pub async fn refresh<R: Runtime>(app: &AppHandle<R>) -> Vec<ManifestEntry> {
+    let discovery = app.state::<KernelDiscovery>();
+    let _guard = discovery.refresh_lock.lock().await;
+
+    let scan_app = app.clone();
+    let candidates = tauri::async_runtime::spawn_blocking(move || find_candidates(&scan_app))
+        .await
+        .unwrap_or_default();
+
+    let previous: Vec<ManifestEntry> = discovery
+        .manifest
+        .lock()
+        .map(|m| m.kernels.clone())
+        .unwrap_or_default();
+
+    let mut entries = Vec::new();
+    let mut to_probe = Vec::new();
+    for path in candidates {
+        let Some((size, modified_ms)) = file_stamp(&path) else {
+            continue;
+        };
+        let known = previous.iter().find(|e| e.path == path);
+        match known {
+            // A failed probe (e.g. a first-run antivirus scan outlasting the timeout) is retried
+            Some(entry) if entry.size == size && entry.modified_ms == modified_ms && entry.version.is_some() => {
+                entries.push(entry.clone())
+            }
+            _ => to_probe.push((path, size, modified_ms, known.cloned())),
+        }
+    }
+
+    if !to_probe.is_empty() {
+        log::info!("Probing {} new, changed or unversioned kernel(s)", to_probe.len());
+        let probed: Vec<ManifestEntry> = stream::iter(to_probe)
+            .map(|(path, size, modified_ms, previous)| probe(path, size, modified_ms, previous))
+            .buffer_unordered(get_probe_concurrency().max(1))
+            .collect()
+            .await;
+        entries.extend(probed);
+    }
+
+    let manifest = KernelManifest {
+        format: MANIFEST_FORMAT,
+        kernels: entries.clone(),
+    };
+    discovery.save(&manifest);
+    if let Ok(mut current) = discovery.manifest.lock() {
+        *current = manifest;
+    }
+    entries
+}
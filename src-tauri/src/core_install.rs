@@ -0,0 +1,1017 @@
+use std::fmt;
+use std::ffi::OsStr;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+use crate::core_discovery::{kernel_exe_name, parse_version};
+use crate::core_inventory::compare_versions;
+
+// Written into a kernel directory as the very last install step. Directories
+// under app_data/core/kernels without it are leftovers of an interrupted install.
+pub const INSTALL_MARKER: &str = ".install-complete";
+const STAGING_PREFIX: &str = ".staging-";
+const KERNELS_DIR: &str = "kernels";
+
+pub const CORE_INSTALL_PROGRESS_EVENT: &str = "core:install-progress";
+
+// Byte progress is throttled to this interval, phase changes are always sent
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+// Where to fetch a kernel when none is bundled for this platform. `{platform}` is
+// replaced with the platform name, e.g. https://example.com/yuHai-core-{platform}.zip.
+// Must be https, and `<url>.sha256` has to publish the archive's digest.
+fn get_core_download_url() -> Option<&'static str> {
+    option_env!("VITE_CORE_DOWNLOAD_URL").filter(|v| !v.trim().is_empty())
+}
+
+// Same `{os}-{arch}` naming as build.rs::detect_platform
+pub fn current_platform() -> String {
+    if let Some(platform) = option_env!("VITE_CORE_PLATFORM") {
+        return platform.to_string();
+    }
+    let os = match std::env::consts::OS {
+        "windows" => "win",
+        "linux" => "linux",
+        "macos" => "macos",
+        _ => "unknown",
+    };
+    let arch = match std::env::consts::ARCH {
+        "x86_64" => "x64",
+        "aarch64" => "arm64",
+        "x86" => "x86",
+        "arm" => "arm",
+        _ => "unknown",
+    };
+    format!("{}-{}", os, arch)
+}
+
+pub fn kernel_download_url() -> Option<String> {
+    get_core_download_url().map(|template| template.replace("{platform}", &current_platform()))
+}
+
+#[derive(Debug)]
+pub enum InstallError {
+    Io { path: PathBuf, source: std::io::Error },
+    Archive(String),
+    // Entry with an absolute path or `..` components
+    UnsafePath(String),
+    // Symlink entry pointing outside the kernel directory
+    EscapingSymlink { entry: String, target: String },
+    MissingExecutable(PathBuf),
+    NotExecutable(PathBuf),
+    Verification(String),
+    // This build ships no kernel for the running platform
+    NoKernelForPlatform { platform: String, path: PathBuf },
+    Download(String),
+    ChecksumMismatch { expected: String, actual: String },
+    InProgress,
+}
+
+impl fmt::Display for InstallError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InstallError::Io { path, source } => write!(f, "I/O error at {:?}: {}", path, source),
+            InstallError::Archive(e) => write!(f, "Invalid kernel archive: {}", e),
+            InstallError::UnsafePath(entry) => write!(f, "Archive entry escapes the install dir: {}", entry),
+            InstallError::EscapingSymlink { entry, target } => {
+                write!(f, "Archive symlink {} points outside the install dir: {}", entry, target)
+            }
+            InstallError::MissingExecutable(path) => write!(f, "Kernel archive does not contain {:?}", path),
+            InstallError::NotExecutable(path) => write!(f, "Kernel executable {:?} is not runnable", path),
+            InstallError::Verification(e) => write!(f, "Extracted kernel failed verification: {}", e),
+            InstallError::NoKernelForPlatform { platform, path } => {
+                write!(f, "No kernel for {}: {:?} is not bundled with this build", platform, path)
+            }
+            InstallError::Download(e) => write!(f, "Failed to download kernel: {}", e),
+            InstallError::ChecksumMismatch { expected, actual } => {
+                write!(f, "Downloaded kernel has SHA-256 {}, expected {}", actual, expected)
+            }
+            InstallError::InProgress => write!(f, "Another kernel installation is in progress"),
+        }
+    }
+}
+
+impl std::error::Error for InstallError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            InstallError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+fn io_error(path: &Path) -> impl FnOnce(std::io::Error) -> InstallError + '_ {
+    move |source| InstallError::Io { path: path.to_path_buf(), source }
+}
+
+#[derive(Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum InstallPhase {
+    #[default]
+    Idle,
+    Checking,
+    Downloading,
+    Extracting,
+    Verifying,
+    Done,
+    Failed,
+}
+
+impl InstallPhase {
+    fn is_active(&self) -> bool {
+        matches!(
+            self,
+            InstallPhase::Checking | InstallPhase::Downloading | InstallPhase::Extracting | InstallPhase::Verifying
+        )
+    }
+}
+
+#[derive(Serialize, Clone, Copy, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtractProgress {
+    pub files_done: u64,
+    pub files_total: u64,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallStatus {
+    pub phase: InstallPhase,
+    #[serde(flatten)]
+    pub progress: ExtractProgress,
+    pub version: Option<String>,
+    pub error: Option<String>,
+    pub platform: String,
+    // Offered when no kernel is bundled for this platform, see `download_kernel`
+    pub download_url: Option<String>,
+    pub updated_at: i64,
+}
+
+pub struct InstallState {
+    status: Mutex<InstallStatus>,
+    // Held for the duration of an archive install
+    busy: AtomicBool,
+}
+
+impl InstallState {
+    pub fn new() -> Self {
+        Self {
+            status: Mutex::new(InstallStatus {
+                platform: current_platform(),
+                ..Default::default()
+            }),
+            busy: AtomicBool::new(false),
+        }
+    }
+}
+
+fn update_status<R: Runtime>(app: &AppHandle<R>, change: impl FnOnce(&mut InstallStatus)) {
+    let Some(state) = app.try_state::<InstallState>() else {
+        return;
+    };
+    let snapshot = match state.status.lock() {
+        Ok(mut status) => {
+            change(&mut status);
+            status.updated_at = chrono::Local::now().timestamp_millis();
+            status.clone()
+        }
+        Err(_) => return,
+    };
+    let _ = app.emit(CORE_INSTALL_PROGRESS_EVENT, snapshot);
+}
+
+fn set_phase<R: Runtime>(app: &AppHandle<R>, phase: InstallPhase) {
+    update_status(app, |status| status.phase = phase);
+}
+
+fn set_failed<R: Runtime>(app: &AppHandle<R>, error: &InstallError) {
+    let message = error.to_string();
+    let download_url = match error {
+        InstallError::NoKernelForPlatform { .. } => kernel_download_url(),
+        _ => None,
+    };
+    update_status(app, |status| {
+        status.phase = InstallPhase::Failed;
+        status.error = Some(message);
+        status.download_url = download_url;
+    });
+}
+
+// Start a new install run unless one is already going
+fn try_begin<R: Runtime>(app: &AppHandle<R>, phase: InstallPhase) -> Result<(), InstallError> {
+    if let Some(state) = app.try_state::<InstallState>() {
+        let mut status = state.status.lock().map_err(|_| InstallError::InProgress)?;
+        if status.phase.is_active() {
+            return Err(InstallError::InProgress);
+        }
+        *status = InstallStatus {
+            phase,
+            platform: current_platform(),
+            ..Default::default()
+        };
+    }
+    set_phase(app, phase);
+    Ok(())
+}
+
+// True while the first-run install or an upgrade is still running
+pub fn is_installing<R: Runtime>(app: &AppHandle<R>) -> bool {
+    app.try_state::<InstallState>()
+        .and_then(|state| state.status.lock().ok().map(|s| s.phase.is_active()))
+        .unwrap_or(false)
+}
+
+#[tauri::command]
+pub fn get_install_status(state: tauri::State<'_, InstallState>) -> Result<InstallStatus, String> {
+    state
+        .status
+        .lock()
+        .map(|s| s.clone())
+        .map_err(|e| e.to_string())
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallMarker {
+    pub version: Option<String>,
+    pub installed_at: i64,
+    // Archive the kernel was installed from
+    pub source: String,
+}
+
+pub fn core_dir<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join("core"))
+        .map_err(|e| format!("App data dir not available: {}", e))
+}
+
+// Versioned kernel directories live here, one per install
+pub fn kernels_dir<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    Ok(core_dir(app)?.join(KERNELS_DIR))
+}
+
+pub fn is_staging_dir(dir: &Path) -> bool {
+    dir.file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| n.starts_with(STAGING_PREFIX))
+}
+
+// Kernels under the versioned layout only count once their install completed.
+// Older flat installs and custom install dirs carry no marker and are trusted.
+pub fn is_usable_kernel_dir(kernels_root: &Path, dir: &Path) -> bool {
+    !dir.starts_with(kernels_root) || dir.join(INSTALL_MARKER).is_file()
+}
+
+// yuHai-core-{platform}.zip, a configured name is only used when it is meant for
+// the platform the shell runs on
+fn bundled_zip_name() -> String {
+    let platform = current_platform();
+    match option_env!("VITE_CORE_ZIP_NAME") {
+        Some(name) if name.contains(&platform) => name.to_string(),
+        _ => format!("yuHai-core-{}.zip", platform),
+    }
+}
+
+// Kernel zip shipped with the app for this platform
+pub fn bundled_kernel_zip<R: Runtime>(app: &AppHandle<R>) -> PathBuf {
+    let kernel_zip_name = bundled_zip_name();
+    let kernel_zip_relative = PathBuf::from("resources").join(&kernel_zip_name);
+
+    log::info!("Platform-specific kernel zip name: {}", kernel_zip_name);
+
+    #[cfg(debug_assertions)]
+    // In development, the resource path is relative to the Cargo.toml location (src-tauri)
+    let resource_path = {
+        let _ = app;
+        kernel_zip_relative
+    };
+
+    #[cfg(not(debug_assertions))]
+    let resource_path = app.path().resolve(kernel_zip_relative, tauri::path::BaseDirectory::Resource)
+        .unwrap_or_else(|_| PathBuf::from(&kernel_zip_name));
+
+    // Try to resolve absolute path for better debugging
+    if resource_path.is_absolute() {
+        resource_path
+    } else {
+        std::env::current_dir().unwrap_or_default().join(&resource_path)
+    }
+}
+
+#[derive(Deserialize)]
+struct ArchiveManifest {
+    #[serde(default)]
+    version: Option<String>,
+}
+
+// Version declared by the integrity manifest inside a kernel zip, read without
+// extracting anything. None when the archive ships no manifest or no version.
+pub fn archive_version(zip_path: &Path) -> Result<Option<semver::Version>, InstallError> {
+    let file = std::fs::File::open(zip_path).map_err(io_error(zip_path))?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| InstallError::Archive(e.to_string()))?;
+    let mut entry = match archive.by_name(crate::core_integrity::get_core_integrity_manifest()) {
+        Ok(entry) => entry,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(InstallError::Archive(e.to_string())),
+    };
+    let mut content = String::new();
+    entry.read_to_string(&mut content).map_err(io_error(zip_path))?;
+    let manifest: ArchiveManifest =
+        serde_json::from_str(&content).map_err(|e| InstallError::Archive(format!("Invalid manifest: {}", e)))?;
+    Ok(manifest.version.as_deref().and_then(parse_version))
+}
+
+pub fn read_marker(dir: &Path) -> Option<InstallMarker> {
+    let content = std::fs::read_to_string(dir.join(INSTALL_MARKER)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+// Newest installed version, from install markers or the discovery cache so no
+// kernel binary has to run during setup
+fn newest_installed_version<R: Runtime>(app: &AppHandle<R>) -> Option<semver::Version> {
+    let cached = crate::core_discovery::cached(app);
+    crate::core_discovery::find_candidates(app)
+        .into_iter()
+        .filter_map(|exe| {
+            let dir = exe.parent()?;
+            read_marker(dir)
+                .and_then(|m| m.version)
+                .or_else(|| cached.iter().find(|e| e.path == exe).and_then(|e| e.version.clone()))
+                .as_deref()
+                .and_then(parse_version)
+        })
+        .max_by(|a, b| compare_versions(Some(a), Some(b)))
+}
+
+// Staging directories of installs that never finished
+fn remove_stale_staging(core_dir: &Path) {
+    let Ok(entries) = std::fs::read_dir(core_dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() && is_staging_dir(&path) {
+            log::warn!("Removing leftover kernel staging dir {:?}", path);
+            let _ = std::fs::remove_dir_all(&path);
+        }
+    }
+}
+
+const S_IFMT: u32 = 0o170000;
+const S_IFLNK: u32 = 0o120000;
+
+const CENTRAL_HEADER_SIGNATURE: [u8; 4] = [0x50, 0x4b, 0x01, 0x02];
+const ZIP_HOST_UNIX: u8 = 3;
+
+// Apply `path` to `parts` resolving `.` and `..` lexically. False when it is
+// absolute or climbs above the start.
+fn push_lexical<'a>(parts: &mut Vec<&'a OsStr>, path: &'a Path) -> bool {
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => parts.push(part),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if parts.pop().is_none() {
+                    return false;
+                }
+            }
+            Component::RootDir | Component::Prefix(_) => return false,
+        }
+    }
+    true
+}
+
+// Archive entry name without `.` and `..`, None when it leaves the extraction root
+fn normalize_entry(name: &Path) -> Option<PathBuf> {
+    let mut parts = Vec::new();
+    if !push_lexical(&mut parts, name) || parts.is_empty() {
+        return None;
+    }
+    Some(parts.iter().collect())
+}
+
+// Resolve a symlink target lexically against the link's own directory and make
+// sure it stays inside the extraction root
+fn symlink_stays_inside(link: &Path, target: &str) -> bool {
+    let mut parts = Vec::new();
+    if !push_lexical(&mut parts, link) || parts.pop().is_none() {
+        return false;
+    }
+    push_lexical(&mut parts, Path::new(target))
+}
+
+// Host system from the "version made by" field of the entry's central directory
+// header. zip 0.6 makes up a 0o664 mode for entries zipped on Windows, only modes
+// of Unix-made entries are real.
+fn made_on_unix(raw: &mut std::fs::File, central_header_start: u64) -> bool {
+    let mut header = [0u8; 6];
+    raw.seek(SeekFrom::Start(central_header_start)).is_ok()
+        && raw.read_exact(&mut header).is_ok()
+        && header[..4] == CENTRAL_HEADER_SIGNATURE
+        && header[5] == ZIP_HOST_UNIX
+}
+
+#[derive(Default)]
+struct ExtractSummary {
+    // Files without a trustworthy Unix mode (zipped on Windows)
+    without_mode: Vec<PathBuf>,
+}
+
+// Entry by entry extraction. Every path must stay below `dest`; symlinks are
+// created last so no file write can be redirected through one.
+fn extract(
+    zip_path: &Path,
+    dest: &Path,
+    report: &mut dyn FnMut(&ExtractProgress),
+) -> Result<ExtractSummary, InstallError> {
+    let file = std::fs::File::open(zip_path).map_err(io_error(zip_path))?;
+    let mut raw = std::fs::File::open(zip_path).map_err(io_error(zip_path))?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| InstallError::Archive(e.to_string()))?;
+    let mut summary = ExtractSummary::default();
+    let mut symlinks = Vec::new();
+
+    let mut progress = ExtractProgress::default();
+    for index in 0..archive.len() {
+        let entry = archive.by_index_raw(index).map_err(|e| InstallError::Archive(e.to_string()))?;
+        if !entry.is_dir() {
+            progress.files_total += 1;
+            progress.bytes_total += entry.size();
+        }
+    }
+    report(&progress);
+    let mut buf = vec![0u8; 64 * 1024];
+
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index).map_err(|e| InstallError::Archive(e.to_string()))?;
+        let name = entry.name().to_string();
+        let relative = entry
+            .enclosed_name()
+            .and_then(normalize_entry)
+            .ok_or_else(|| InstallError::UnsafePath(name.clone()))?;
+        let path = dest.join(&relative);
+        let mode = if made_on_unix(&mut raw, entry.central_header_start()) {
+            entry.unix_mode()
+        } else {
+            None
+        };
+
+        if entry.is_dir() {
+            std::fs::create_dir_all(&path).map_err(io_error(&path))?;
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(io_error(parent))?;
+        }
+
+        if mode.is_some_and(|m| m & S_IFMT == S_IFLNK) {
+            let mut target = String::new();
+            entry.read_to_string(&mut target).map_err(io_error(&path))?;
+            if !symlink_stays_inside(&relative, &target) {
+                return Err(InstallError::EscapingSymlink { entry: name, target });
+            }
+            symlinks.push((path, target));
+            progress.files_done += 1;
+            progress.bytes_done += entry.size();
+            report(&progress);
+            continue;
+        }
+
+        let mut out = std::fs::File::create(&path).map_err(io_error(&path))?;
+        loop {
+            let read = entry.read(&mut buf).map_err(io_error(&path))?;
+            if read == 0 {
+                break;
+            }
+            out.write_all(&buf[..read]).map_err(io_error(&path))?;
+            progress.bytes_done += read as u64;
+            report(&progress);
+        }
+        progress.files_done += 1;
+        report(&progress);
+        match mode {
+            #[cfg(unix)]
+            Some(mode) => {
+                use std::os::unix::fs::PermissionsExt;
+                // No setuid/setgid/sticky bits from an archive
+                std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode & 0o777))
+                    .map_err(io_error(&path))?;
+            }
+            #[cfg(not(unix))]
+            Some(_) => {}
+            None => summary.without_mode.push(path),
+        }
+    }
+
+    #[cfg(unix)]
+    {
+        for (path, target) in &symlinks {
+            std::os::unix::fs::symlink(target, path).map_err(io_error(path))?;
+        }
+        // Each target was checked on its own, a chain of links can still climb out
+        let root = std::fs::canonicalize(dest).map_err(io_error(dest))?;
+        for (path, target) in symlinks {
+            if std::fs::canonicalize(&path).is_ok_and(|resolved| !resolved.starts_with(&root)) {
+                let entry = path.strip_prefix(dest).unwrap_or(&path).to_string_lossy().to_string();
+                return Err(InstallError::EscapingSymlink { entry, target });
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    for (path, target) in symlinks {
+        log::warn!("Skipping symlink {:?} -> {} in kernel archive", path, target);
+    }
+    Ok(summary)
+}
+
+// The kernel executable must exist and be executable. Entries zipped on Windows
+// have no real Unix mode, their executable is made runnable here; a Unix mode
+// without execute bits is an error.
+fn ensure_runnable(exe: &Path, summary: &ExtractSummary) -> Result<(), InstallError> {
+    let metadata = std::fs::metadata(exe).map_err(|_| InstallError::MissingExecutable(exe.to_path_buf()))?;
+    if !metadata.is_file() || metadata.len() == 0 {
+        return Err(InstallError::NotExecutable(exe.to_path_buf()));
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if metadata.permissions().mode() & 0o111 == 0 {
+            if !summary.without_mode.iter().any(|p| p == exe) {
+                return Err(InstallError::NotExecutable(exe.to_path_buf()));
+            }
+            std::fs::set_permissions(exe, std::fs::Permissions::from_mode(0o755)).map_err(io_error(exe))?;
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = summary;
+    Ok(())
+}
+
+// Check a freshly extracted kernel and return its version, if it declares one
+fn verify_staged(staging: &Path, summary: &ExtractSummary) -> Result<Option<String>, InstallError> {
+    ensure_runnable(&staging.join(kernel_exe_name()), summary)?;
+    let report = crate::core_integrity::verify_kernel_dir(staging);
+    if !report.ok {
+        return Err(InstallError::Verification(report.summary()));
+    }
+    Ok(report.version.as_deref().and_then(parse_version).map(|v| v.to_string()))
+}
+
+// Extract `zip_path` into a staging directory next to the kernels, verify it, mark
+// it complete and rename it into kernels/<version>. Nothing under kernels/ is
+// touched until the new kernel is known to be whole. Progress is published as
+// `core:install-progress`.
+pub fn install_archive<R: Runtime>(app: &AppHandle<R>, zip_path: &Path) -> Result<PathBuf, InstallError> {
+    let state = app.try_state::<InstallState>();
+    if let Some(state) = &state {
+        if state.busy.swap(true, Ordering::SeqCst) {
+            return Err(InstallError::InProgress);
+        }
+    }
+    let result = install_archive_locked(app, zip_path);
+    match &result {
+        Ok(_) => {
+            set_phase(app, InstallPhase::Done);
+            // Every install adds a kernel directory, let the retention policy trim the old ones
+            crate::core_manager::schedule_cleanup(app, "Post-install");
+        }
+        Err(e) => set_failed(app, e),
+    }
+    if let Some(state) = &state {
+        state.busy.store(false, Ordering::SeqCst);
+    }
+    result
+}
+
+fn install_archive_locked<R: Runtime>(app: &AppHandle<R>, zip_path: &Path) -> Result<PathBuf, InstallError> {
+    let bundled = archive_version(zip_path).ok().flatten().map(|v| v.to_string());
+    update_status(app, |status| {
+        status.phase = InstallPhase::Extracting;
+        status.progress = ExtractProgress::default();
+        status.version = bundled;
+        status.error = None;
+    });
+
+    let core_dir = core_dir(app).map_err(|e| InstallError::Io {
+        path: PathBuf::new(),
+        source: std::io::Error::other(e),
+    })?;
+    let kernels_dir = core_dir.join(KERNELS_DIR);
+    std::fs::create_dir_all(&kernels_dir).map_err(io_error(&kernels_dir))?;
+    remove_stale_staging(&core_dir);
+
+    let staging = core_dir.join(format!(
+        "{}{}-{}",
+        STAGING_PREFIX,
+        std::process::id(),
+        chrono::Local::now().timestamp_millis()
+    ));
+    std::fs::create_dir_all(&staging).map_err(io_error(&staging))?;
+    log::info!("Extracting {:?} into {:?}", zip_path, staging);
+
+    let mut last_emit: Option<Instant> = None;
+    let mut report = |progress: &ExtractProgress| {
+        let finished = progress.files_done == progress.files_total;
+        if finished || last_emit.is_none_or(|at| at.elapsed() >= PROGRESS_INTERVAL) {
+            last_emit = Some(Instant::now());
+            let progress = *progress;
+            update_status(app, |status| status.progress = progress);
+        }
+    };
+    let staged = extract(zip_path, &staging, &mut report)
+        .and_then(|summary| {
+            set_phase(app, InstallPhase::Verifying);
+            verify_staged(&staging, &summary)
+        })
+        .and_then(|version| {
+            let marker = InstallMarker {
+                version: version.clone(),
+                installed_at: chrono::Local::now().timestamp_millis(),
+                source: zip_path.to_string_lossy().to_string(),
+            };
+            let marker_path = staging.join(INSTALL_MARKER);
+            let content = serde_json::to_string_pretty(&marker).map_err(|e| InstallError::Io {
+                path: marker_path.clone(),
+                source: e.into(),
+            })?;
+            std::fs::write(&marker_path, content).map_err(io_error(&marker_path))?;
+            Ok(version)
+        });
+    let version = match staged {
+        Ok(version) => version,
+        Err(e) => {
+            let _ = std::fs::remove_dir_all(&staging);
+            return Err(e);
+        }
+    };
+
+    let target = kernels_dir.join(version.as_deref().unwrap_or("bundled"));
+    if target.exists() {
+        // Partial leftover or a reinstall of the same version
+        log::info!("Replacing existing kernel dir {:?}", target);
+        if let Err(e) = std::fs::remove_dir_all(&target) {
+            let _ = std::fs::remove_dir_all(&staging);
+            return Err(io_error(&target)(e));
+        }
+    }
+    if let Err(e) = std::fs::rename(&staging, &target) {
+        let _ = std::fs::remove_dir_all(&staging);
+        return Err(io_error(&target)(e));
+    }
+    log::info!("Installed kernel {:?} to {:?}", version, target);
+    Ok(target)
+}
+
+// Install the bundled kernel when none is installed yet, or next to the existing
+// ones when the bundle is newer than all of them. Older kernels stay in place so
+// the user can roll back; retention cleanup decides when they go.
+pub fn install_or_upgrade_bundled<R: Runtime>(app: &AppHandle<R>) -> Result<Option<PathBuf>, InstallError> {
+    try_begin(app, InstallPhase::Checking)?;
+    let result = check_bundled(app);
+    // install_archive settles the phase itself when it ran
+    match &result {
+        Ok(None) => set_phase(app, InstallPhase::Done),
+        Err(e) if !matches!(e, InstallError::InProgress) && is_installing(app) => set_failed(app, e),
+        _ => {}
+    }
+    result
+}
+
+fn check_bundled<R: Runtime>(app: &AppHandle<R>) -> Result<Option<PathBuf>, InstallError> {
+    if !crate::core_discovery::has_any_kernel(app) {
+        return install_bundled(app).map(Some);
+    }
+    let zip_path = bundled_kernel_zip(app);
+    if !zip_path.exists() {
+        log::info!("No bundled kernel at {:?}, keeping installed kernels", zip_path);
+        return Ok(None);
+    }
+    let Some(bundled) = archive_version(&zip_path)? else {
+        log::info!("Bundled kernel declares no version, keeping installed kernels");
+        return Ok(None);
+    };
+    match newest_installed_version(app) {
+        Some(installed) if compare_versions(Some(&bundled), Some(&installed)).is_le() => {
+            log::info!("Installed kernel {} is up to date (bundled {})", installed, bundled);
+            Ok(None)
+        }
+        installed => {
+            log::info!("Upgrading kernel {:?} to bundled {}", installed.map(|v| v.to_string()), bundled);
+            install_archive(app, &zip_path).map(Some)
+        }
+    }
+}
+
+pub fn install_bundled<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, InstallError> {
+    let zip_path = bundled_kernel_zip(app);
+    if !zip_path.exists() {
+        return Err(InstallError::NoKernelForPlatform {
+            platform: current_platform(),
+            path: zip_path,
+        });
+    }
+    log::info!("Found bundled kernel zip at: {:?}", zip_path);
+    install_archive(app, &zip_path)
+}
+
+// `<url>.sha256`, either the bare digest or sha256sum output
+async fn fetch_checksum(client: &reqwest::Client, url: &str) -> Result<String, InstallError> {
+    let checksum_url = format!("{}.sha256", url);
+    let body = client
+        .get(&checksum_url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| InstallError::Download(format!("no checksum at {}: {}", checksum_url, e)))?
+        .text()
+        .await
+        .map_err(|e| InstallError::Download(e.to_string()))?;
+    body.split_whitespace()
+        .next()
+        .filter(|digest| digest.len() == 64 && digest.chars().all(|c| c.is_ascii_hexdigit()))
+        .map(|digest| digest.to_ascii_lowercase())
+        .ok_or_else(|| InstallError::Download(format!("invalid checksum at {}", checksum_url)))
+}
+
+async fn download_archive<R: Runtime>(app: &AppHandle<R>, url: &str, dest: &Path) -> Result<(), InstallError> {
+    use sha2::{Digest, Sha256};
+
+    let client = reqwest::Client::builder()
+        .https_only(true)
+        .connect_timeout(Duration::from_secs(10))
+        .build()
+        .map_err(|e| InstallError::Download(e.to_string()))?;
+    let expected = fetch_checksum(&client, url).await?;
+    let mut response = client
+        .get(url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| InstallError::Download(e.to_string()))?;
+
+    let mut progress = ExtractProgress {
+        files_total: 1,
+        bytes_total: response.content_length().unwrap_or(0),
+        ..Default::default()
+    };
+    let mut file = std::fs::File::create(dest).map_err(io_error(dest))?;
+    let mut hasher = Sha256::new();
+    let mut last_emit = Instant::now();
+    while let Some(chunk) = response.chunk().await.map_err(|e| InstallError::Download(e.to_string()))? {
+        file.write_all(&chunk).map_err(io_error(dest))?;
+        hasher.update(&chunk);
+        progress.bytes_done += chunk.len() as u64;
+        if last_emit.elapsed() >= PROGRESS_INTERVAL {
+            last_emit = Instant::now();
+            update_status(app, |status| status.progress = progress);
+        }
+    }
+    progress.files_done = 1;
+    update_status(app, |status| status.progress = progress);
+
+    let actual = hex::encode(hasher.finalize());
+    if actual != expected {
+        return Err(InstallError::ChecksumMismatch { expected, actual });
+    }
+    Ok(())
+}
+
+// Download the kernel archive at `url` and install it, returning the kernel directory
+pub async fn download_and_install<R: Runtime>(app: &AppHandle<R>, url: &str) -> Result<PathBuf, String> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| format!("Invalid kernel download URL {}: {}", url, e))?;
+    if parsed.scheme() != "https" {
+        return Err(format!("Kernel downloads must use https: {}", url));
+    }
+    let core_dir = core_dir(app)?;
+    std::fs::create_dir_all(&core_dir).map_err(|e| format!("Failed to create {:?}: {}", core_dir, e))?;
+    let archive = core_dir.join(format!("yuHai-core-{}.zip.download", current_platform()));
+
+    try_begin(app, InstallPhase::Downloading).map_err(|e| e.to_string())?;
+    update_status(app, |status| status.download_url = Some(url.to_string()));
+    log::info!("Downloading kernel from {}", url);
+    if let Err(e) = download_archive(app, url, &archive).await {
+        let _ = std::fs::remove_file(&archive);
+        set_failed(app, &e);
+        return Err(e.to_string());
+    }
+
+    let install_app = app.clone();
+    let install_archive_path = archive.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || install_archive(&install_app, &install_archive_path))
+        .await
+        .unwrap_or_else(|e| {
+            let e = InstallError::Io {
+                path: archive.clone(),
+                source: std::io::Error::other(e.to_string()),
+            };
+            set_failed(app, &e);
+            Err(e)
+        });
+    let _ = std::fs::remove_file(&archive);
+    result.map_err(|e| e.to_string())
+}
+
+// Fetch the kernel for this platform when the build ships none, install it and start it
+#[tauri::command]
+pub async fn download_kernel<R: Runtime>(app: AppHandle<R>) -> Result<crate::core_readiness::ReadinessReport, String> {
+    let url = kernel_download_url()
+        .ok_or_else(|| format!("No kernel download source is configured for {}", current_platform()))?;
+    download_and_install(&app, &url).await?;
+
+    crate::core_discovery::refresh(&app).await;
+    crate::core_manager::launch_core(&app).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zip::write::FileOptions;
+
+    struct Scratch(PathBuf);
+
+    impl Scratch {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("yuhai-install-{}-{}", name, std::process::id()));
+            let _ = std::fs::remove_dir_all(&dir);
+            std::fs::create_dir_all(&dir).unwrap();
+            Scratch(dir)
+        }
+    }
+
+    impl Drop for Scratch {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn stored() -> FileOptions {
+        FileOptions::default().compression_method(zip::CompressionMethod::Stored)
+    }
+
+    // Rewrite the central directory header of entry `index`
+    fn patch_central(zip_path: &Path, index: usize, patch: impl FnOnce(&mut [u8; 42])) {
+        let start = {
+            let mut archive = zip::ZipArchive::new(std::fs::File::open(zip_path).unwrap()).unwrap();
+            let entry = archive.by_index_raw(index).unwrap();
+            entry.central_header_start()
+        };
+        let mut file = std::fs::OpenOptions::new().read(true).write(true).open(zip_path).unwrap();
+        let mut header = [0u8; 42];
+        file.seek(SeekFrom::Start(start)).unwrap();
+        file.read_exact(&mut header).unwrap();
+        patch(&mut header);
+        file.seek(SeekFrom::Start(start)).unwrap();
+        file.write_all(&header).unwrap();
+    }
+
+    // Make an entry look like it was zipped on Windows: DOS host, archive attribute only
+    fn made_on_windows(header: &mut [u8; 42]) {
+        header[5] = 0;
+        header[38..42].copy_from_slice(&0x20u32.to_le_bytes());
+    }
+
+    fn unix_mode(mode: u32) -> impl FnOnce(&mut [u8; 42]) {
+        move |header| header[38..42].copy_from_slice(&(mode << 16).to_le_bytes())
+    }
+
+    fn write_zip(zip_path: &Path, build: impl FnOnce(&mut zip::ZipWriter<std::fs::File>)) {
+        let mut writer = zip::ZipWriter::new(std::fs::File::create(zip_path).unwrap());
+        build(&mut writer);
+        writer.finish().unwrap();
+    }
+
+    #[cfg(unix)]
+    fn mode_of(path: &Path) -> u32 {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::symlink_metadata(path).unwrap().permissions().mode() & 0o7777
+    }
+
+    #[test]
+    fn normalize_entry_resolves_dots_inside_root() {
+        assert_eq!(normalize_entry(Path::new("a/./b/../c")), Some(PathBuf::from("a/c")));
+        assert_eq!(normalize_entry(Path::new("../x")), None);
+        assert_eq!(normalize_entry(Path::new("a/../../x")), None);
+        assert_eq!(normalize_entry(Path::new("/etc/passwd")), None);
+        assert_eq!(normalize_entry(Path::new("a/..")), None);
+    }
+
+    #[test]
+    fn symlink_targets_must_stay_inside() {
+        assert!(symlink_stays_inside(Path::new("lib/link"), "libfoo.so.1"));
+        assert!(symlink_stays_inside(Path::new("a/b/link"), "../../top"));
+        assert!(symlink_stays_inside(Path::new("a/b/../link"), "../x"));
+        assert!(!symlink_stays_inside(Path::new("link"), "../x"));
+        assert!(!symlink_stays_inside(Path::new("a/b/link"), "../../../x"));
+        assert!(!symlink_stays_inside(Path::new("a/link"), "/etc/passwd"));
+        // `..` in the link's own name must not count as depth
+        assert!(!symlink_stays_inside(Path::new("a/b/../link"), "../../x"));
+        assert!(!symlink_stays_inside(Path::new("a/b/../link"), "../../../x"));
+    }
+
+    #[test]
+    fn rejects_entries_outside_root() {
+        let scratch = Scratch::new("slip");
+        let zip_path = scratch.0.join("k.zip");
+        write_zip(&zip_path, |w| {
+            w.start_file("../evil", stored()).unwrap();
+            w.write_all(b"x").unwrap();
+        });
+        let dest = scratch.0.join("out");
+        std::fs::create_dir_all(&dest).unwrap();
+        let result = extract(&zip_path, &dest, &mut |_| {});
+        assert!(matches!(result, Err(InstallError::UnsafePath(_))));
+        assert!(!scratch.0.join("evil").exists());
+    }
+
+    #[test]
+    fn rejects_symlink_hidden_behind_dotdot_in_name() {
+        let scratch = Scratch::new("link");
+        let zip_path = scratch.0.join("k.zip");
+        write_zip(&zip_path, |w| {
+            w.add_symlink("a/b/../link", "../../../x", stored()).unwrap();
+        });
+        let dest = scratch.0.join("out");
+        std::fs::create_dir_all(&dest).unwrap();
+        let result = extract(&zip_path, &dest, &mut |_| {});
+        assert!(matches!(result, Err(InstallError::EscapingSymlink { .. })));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_symlink_chain_leaving_root() {
+        let scratch = Scratch::new("chain");
+        let zip_path = scratch.0.join("k.zip");
+        write_zip(&zip_path, |w| {
+            w.add_symlink("a/up", "..", stored()).unwrap();
+            w.add_symlink("a/out", "up/..", stored()).unwrap();
+        });
+        let dest = scratch.0.join("out");
+        std::fs::create_dir_all(&dest).unwrap();
+        let result = extract(&zip_path, &dest, &mut |_| {});
+        assert!(matches!(result, Err(InstallError::EscapingSymlink { .. })));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn restores_unix_modes_without_special_bits() {
+        let scratch = Scratch::new("modes");
+        let zip_path = scratch.0.join("k.zip");
+        write_zip(&zip_path, |w| {
+            w.start_file("yuHai", stored()).unwrap();
+            w.write_all(b"#!/bin/sh\n").unwrap();
+            w.start_file("data.txt", stored().unix_permissions(0o600)).unwrap();
+            w.write_all(b"data").unwrap();
+        });
+        patch_central(&zip_path, 0, unix_mode(0o104755));
+        let dest = scratch.0.join("out");
+        std::fs::create_dir_all(&dest).unwrap();
+        let summary = extract(&zip_path, &dest, &mut |_| {}).unwrap();
+        assert!(summary.without_mode.is_empty());
+        assert_eq!(mode_of(&dest.join("yuHai")), 0o755);
+        assert_eq!(mode_of(&dest.join("data.txt")), 0o600);
+        ensure_runnable(&dest.join("yuHai"), &summary).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn windows_made_executable_becomes_runnable() {
+        let scratch = Scratch::new("windows");
+        let zip_path = scratch.0.join("k.zip");
+        write_zip(&zip_path, |w| {
+            w.start_file("yuHai", stored()).unwrap();
+            w.write_all(b"#!/bin/sh\n").unwrap();
+        });
+        patch_central(&zip_path, 0, made_on_windows);
+        let dest = scratch.0.join("out");
+        std::fs::create_dir_all(&dest).unwrap();
+        let summary = extract(&zip_path, &dest, &mut |_| {}).unwrap();
+        let exe = dest.join("yuHai");
+        assert_eq!(summary.without_mode, vec![exe.clone()]);
+        ensure_runnable(&exe, &summary).unwrap();
+        assert_eq!(mode_of(&exe) & 0o111, 0o111);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unix_made_executable_without_exec_bits_fails() {
+        let scratch = Scratch::new("noexec");
+        let zip_path = scratch.0.join("k.zip");
+        write_zip(&zip_path, |w| {
+            w.start_file("yuHai", stored().unix_permissions(0o644)).unwrap();
+            w.write_all(b"#!/bin/sh\n").unwrap();
+        });
+        let dest = scratch.0.join("out");
+        std::fs::create_dir_all(&dest).unwrap();
+        let summary = extract(&zip_path, &dest, &mut |_| {}).unwrap();
+        let result = ensure_runnable(&dest.join("yuHai"), &summary);
+        assert!(matches!(result, Err(InstallError::NotExecutable(_))));
+    }
+}
@@ -0,0 +1,356 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+use crate::core_discovery::hash_file;
+
+pub const CORE_INTEGRITY_EVENT: &str = "core:integrity";
+
+// Name of the manifest shipped at the root of each kernel zip
+pub fn get_core_integrity_manifest() -> &'static str {
+    option_env!("VITE_CORE_INTEGRITY_MANIFEST").unwrap_or("integrity.json")
+}
+
+// Refuse kernels that ship without a manifest (older releases don't have one)
+fn get_core_require_integrity() -> bool {
+    option_env!("VITE_CORE_REQUIRE_INTEGRITY")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(false)
+}
+
+#[derive(Deserialize)]
+struct FileDigest {
+    sha256: String,
+    #[serde(default)]
+    size: Option<u64>,
+}
+
+// {"version": "1.2.0", "files": {"yuHai.exe": {"sha256": "…", "size": 123}, …}}
+// Paths are relative to the kernel directory and use forward slashes.
+#[derive(Deserialize)]
+struct IntegrityManifest {
+    #[serde(default)]
+    version: Option<String>,
+    files: BTreeMap<String, FileDigest>,
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityReport {
+    pub kernel_dir: String,
+    pub ok: bool,
+    pub manifest_found: bool,
+    pub version: Option<String>,
+    pub checked: usize,
+    pub missing: Vec<String>,
+    pub mismatched: Vec<String>,
+    // A repair can be attempted through `repair_kernel`
+    pub repairable: bool,
+    pub error: Option<String>,
+}
+
+impl IntegrityReport {
+    pub fn summary(&self) -> String {
+        if let Some(error) = &self.error {
+            return error.clone();
+        }
+        format!(
+            "{} missing and {} modified file(s) in {}",
+            self.missing.len(),
+            self.mismatched.len(),
+            self.kernel_dir
+        )
+    }
+}
+
+// Manifest entries must stay inside the kernel directory
+fn safe_relative(path: &str) -> Option<PathBuf> {
+    let relative = Path::new(path);
+    relative
+        .components()
+        .all(|c| matches!(c, Component::Normal(_)))
+        .then(|| relative.to_path_buf())
+}
+
+// Hash every file listed in the kernel directory's manifest and compare
+pub fn verify_kernel_dir(dir: &Path) -> IntegrityReport {
+    let mut report = IntegrityReport {
+        kernel_dir: dir.to_string_lossy().to_string(),
+        ..Default::default()
+    };
+
+    let manifest_path = dir.join(get_core_integrity_manifest());
+    let content = match std::fs::read_to_string(&manifest_path) {
+        Ok(content) => content,
+        Err(_) => {
+            report.ok = !get_core_require_integrity();
+            if !report.ok {
+                report.error = Some(format!("Integrity manifest {:?} is missing", manifest_path));
+            }
+            return report;
+        }
+    };
+    report.manifest_found = true;
+
+    let manifest: IntegrityManifest = match serde_json::from_str(&content) {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            report.error = Some(format!("Integrity manifest is unreadable: {}", e));
+            return report;
+        }
+    };
+    report.version = manifest.version;
+
+    for (name, digest) in &manifest.files {
+        let Some(relative) = safe_relative(name) else {
+            report.mismatched.push(name.clone());
+            continue;
+        };
+        let path = dir.join(relative);
+        let metadata = match std::fs::metadata(&path) {
+            Ok(metadata) if metadata.is_file() => metadata,
+            _ => {
+                report.missing.push(name.clone());
+                continue;
+            }
+        };
+        report.checked += 1;
+        // Cheap size check first, partial copies are the common case
+        if digest.size.is_some_and(|size| size != metadata.len()) {
+            report.mismatched.push(name.clone());
+            continue;
+        }
+        match hash_file(&path) {
+            Ok(hash) if hash.eq_ignore_ascii_case(&digest.sha256) => {}
+            Ok(_) => report.mismatched.push(name.clone()),
+            // Quarantined files often can't be opened at all
+            Err(_) => report.missing.push(name.clone()),
+        }
+    }
+
+    report.ok = report.missing.is_empty() && report.mismatched.is_empty();
+    report
+}
+
+// Size and mtime of the install marker, the manifest and every file it lists
+#[derive(PartialEq, Eq)]
+struct Fingerprint(Vec<(PathBuf, u64, Option<SystemTime>)>);
+
+fn fingerprint(dir: &Path) -> Option<Fingerprint> {
+    let stamp = |path: PathBuf| {
+        let metadata = std::fs::metadata(&path).ok()?;
+        let modified = metadata.modified().ok();
+        Some((path, metadata.len(), modified))
+    };
+    let manifest_path = dir.join(get_core_integrity_manifest());
+    let manifest: IntegrityManifest = serde_json::from_str(&std::fs::read_to_string(&manifest_path).ok()?).ok()?;
+    let mut stamps = Vec::with_capacity(manifest.files.len() + 2);
+    stamps.extend(stamp(dir.join(crate::core_install::INSTALL_MARKER)));
+    stamps.push(stamp(manifest_path)?);
+    for name in manifest.files.keys() {
+        stamps.push(stamp(dir.join(safe_relative(name)?))?);
+    }
+    Some(Fingerprint(stamps))
+}
+
+// Kernel directories that passed verification, so restarts by the supervisor or
+// the watchdog don't hash the whole kernel again while nothing changed on disk
+pub struct IntegrityCache {
+    verified: Mutex<HashMap<PathBuf, (Fingerprint, IntegrityReport)>>,
+}
+
+impl IntegrityCache {
+    pub fn new() -> Self {
+        Self {
+            verified: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // Taken before hashing, so a file changed while it was read doesn't match later
+    fn verify(&self, dir: &Path) -> IntegrityReport {
+        let before = fingerprint(dir);
+        if let Some(fingerprint) = &before {
+            let cached = self.verified.lock().ok().and_then(|verified| {
+                verified
+                    .get(dir)
+                    .filter(|(known, _)| known == fingerprint)
+                    .map(|(_, report)| report.clone())
+            });
+            if let Some(report) = cached {
+                log::info!("Kernel at {:?} is unchanged since it was verified", dir);
+                return report;
+            }
+        }
+
+        let report = verify_kernel_dir(dir);
+        if let Ok(mut verified) = self.verified.lock() {
+            match before {
+                Some(fingerprint) if report.ok && report.manifest_found => {
+                    verified.insert(dir.to_path_buf(), (fingerprint, report.clone()));
+                }
+                _ => {
+                    verified.remove(dir);
+                }
+            }
+        }
+        report
+    }
+}
+
+// Verify the kernel about to be launched, publishing failures to the frontend
+pub async fn check_kernel<R: Runtime>(app: &AppHandle<R>, kernel_path: &Path) -> Result<IntegrityReport, IntegrityReport> {
+    let dir = kernel_path.parent().unwrap_or(kernel_path).to_path_buf();
+    let verify_app = app.clone();
+    let report = tauri::async_runtime::spawn_blocking(move || verify_app.state::<IntegrityCache>().verify(&dir))
+        .await
+        .unwrap_or_else(|e| IntegrityReport {
+            error: Some(e.to_string()),
+            ..Default::default()
+        });
+
+    if report.ok {
+        if report.manifest_found {
+            log::info!("Kernel integrity verified ({} files)", report.checked);
+        } else {
+            log::warn!("Kernel at {:?} has no integrity manifest, skipping verification", kernel_path);
+        }
+        return Ok(report);
+    }
+
+    let report = IntegrityReport {
+        repairable: find_replacement(app, kernel_path).is_some(),
+        ..report
+    };
+    log::error!("Kernel integrity check failed: {}", report.summary());
+    let _ = app.emit(CORE_INTEGRITY_EVENT, report.clone());
+    Err(report)
+}
+
+fn core_dir<R: Runtime>(app: &AppHandle<R>) -> Option<PathBuf> {
+    app.path().app_data_dir().ok().map(|dir| dir.join("core"))
+}
+
+// Updated kernels live in their own directory below app_data/core and can be
+// dropped, falling back to another installed kernel
+fn is_removable<R: Runtime>(app: &AppHandle<R>, kernel_path: &Path) -> bool {
+    let Some(core_dir) = core_dir(app) else {
+        return false;
+    };
+    kernel_path
+        .parent()
+        .is_some_and(|dir| dir.starts_with(&core_dir) && dir != core_dir)
+}
+
+// Removable, or a flat install from before versioned dirs that can be hidden from discovery
+fn is_retirable<R: Runtime>(app: &AppHandle<R>, kernel_path: &Path) -> bool {
+    is_removable(app, kernel_path) || kernel_path.parent() == core_dir(app).as_deref()
+}
+
+// The post-install retention cleanup may have removed it already
+fn retire<R: Runtime>(app: &AppHandle<R>, broken: &Path) -> Result<(), String> {
+    let dir = broken.parent().unwrap_or(broken);
+    let result = if is_removable(app, broken) {
+        log::warn!("Removing broken kernel at {:?}", dir);
+        std::fs::remove_dir_all(dir)
+    } else if core_dir(app).as_deref() == Some(dir) {
+        log::warn!("Retiring broken kernel {:?}", broken);
+        std::fs::remove_file(broken)
+    } else {
+        Ok(())
+    };
+    match result {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(format!("Failed to remove {:?}: {}", dir, e)),
+        _ => Ok(()),
+    }
+}
+
+// Where a kernel to take the broken one's place comes from
+enum Replacement {
+    Bundled,
+    Download(String),
+    // Another installed kernel, picked up once the broken one is gone
+    Installed,
+}
+
+fn find_replacement<R: Runtime>(app: &AppHandle<R>, kernel_path: &Path) -> Option<Replacement> {
+    if crate::core_install::bundled_kernel_zip(app).exists() {
+        return Some(Replacement::Bundled);
+    }
+    if let Some(url) = crate::core_install::kernel_download_url() {
+        return Some(Replacement::Download(url));
+    }
+    let other_installed = crate::core_inventory::scan_kernels(app)
+        .iter()
+        .any(|(path, _)| path != kernel_path);
+    (other_installed && is_retirable(app, kernel_path)).then_some(Replacement::Installed)
+}
+
+// Replace a broken kernel with the bundled one, a fresh download or another installed
+// kernel. The broken kernel is only removed once its replacement is in place, without
+// one it is left alone (its directory may hold data the kernel wrote). The kernel is
+// started again afterwards.
+#[tauri::command]
+pub async fn repair_kernel<R: Runtime>(app: AppHandle<R>) -> Result<IntegrityReport, String> {
+    let running = app
+        .state::<crate::core_manager::CoreState>()
+        .process
+        .lock()
+        .map(|g| g.is_some())
+        .unwrap_or(false);
+    if running {
+        return Err("Stop the kernel before repairing it".to_string());
+    }
+
+    crate::core_discovery::refresh(&app).await;
+    let (kernel_path, _) = crate::core_inventory::find_latest_kernel(&app).ok_or("Kernel executable not found")?;
+
+    let Some(replacement) = find_replacement(&app, &kernel_path) else {
+        let dir = kernel_path.parent().unwrap_or(&kernel_path).to_path_buf();
+        let mut report = tauri::async_runtime::spawn_blocking(move || verify_kernel_dir(&dir))
+            .await
+            .map_err(|e| e.to_string())?;
+        report.repairable = false;
+        if !report.ok && report.error.is_none() {
+            report.error = Some("No bundled, downloadable or other installed kernel can replace it".to_string());
+        }
+        log::error!("Nothing can replace the kernel at {:?}, leaving it in place", kernel_path);
+        return Ok(report);
+    };
+
+    let installed = match replacement {
+        Replacement::Bundled => {
+            log::warn!("Reinstalling the bundled kernel");
+            let install_app = app.clone();
+            let dir = tauri::async_runtime::spawn_blocking(move || crate::core_install::install_bundled(&install_app))
+                .await
+                .map_err(|e| e.to_string())?
+                .map_err(|e| e.to_string())?;
+            Some(dir)
+        }
+        Replacement::Download(url) => Some(crate::core_install::download_and_install(&app, &url).await?),
+        Replacement::Installed => None,
+    };
+
+    // A reinstall of the same version already replaced the broken files
+    if installed.as_deref() != kernel_path.parent() {
+        let retire_app = app.clone();
+        let broken = kernel_path.clone();
+        tauri::async_runtime::spawn_blocking(move || retire(&retire_app, &broken))
+            .await
+            .map_err(|e| e.to_string())??;
+    }
+
+    crate::core_discovery::refresh(&app).await;
+
+    let (kernel_path, _) = crate::core_inventory::find_latest_kernel(&app).ok_or("Kernel executable not found")?;
+    let report = check_kernel(&app, &kernel_path)
+        .await
+        .map_err(|report| format!("Kernel is still damaged after repair: {}", report.summary()))?;
+
+    crate::core_manager::launch_core(&app).await?;
+    Ok(report)
+}
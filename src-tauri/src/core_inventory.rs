@@ -0,0 +1,237 @@
+use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
+
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Runtime};
+
+const PREFERENCES_FILE: &str = "kernel-preferences.json";
+
+fn get_core_allow_prerelease() -> bool {
+    option_env!("VITE_CORE_ALLOW_PRERELEASE")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(false)
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct KernelPreferences {
+    // Version that must be used instead of the newest installed one
+    #[serde(default)]
+    pub pinned_version: Option<String>,
+    // Whether pre-release kernels may be picked automatically, VITE_CORE_ALLOW_PRERELEASE when unset
+    #[serde(default)]
+    pub allow_prerelease: Option<bool>,
+}
+
+impl KernelPreferences {
+    pub fn prerelease_allowed(&self) -> bool {
+        self.allow_prerelease.unwrap_or_else(get_core_allow_prerelease)
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct KernelInfo {
+    pub path: String,
+    pub dir: String,
+    pub version: Option<String>,
+    pub prerelease: bool,
+    pub installed_at: Option<i64>,
+    pub size_bytes: u64,
+    pub pinned: bool,
+    pub active: bool,
+}
+
+// Every known kernel executable with its version, as recorded by the last discovery refresh
+pub fn scan_kernels<R: Runtime>(app: &AppHandle<R>) -> Vec<(PathBuf, Option<Version>)> {
+    crate::core_discovery::cached(app)
+        .into_iter()
+        .map(|entry| {
+            let version = entry.semver();
+            (entry.path, version)
+        })
+        .collect()
+}
+
+// Semver precedence, which ignores build metadata. Ties are broken on the full
+// version so the order stays stable; unversioned kernels sort last.
+pub fn compare_versions(a: Option<&Version>, b: Option<&Version>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => a.cmp_precedence(b).then_with(|| a.cmp(b)),
+        (Some(_), None) => Ordering::Greater,
+        (None, Some(_)) => Ordering::Less,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+// The pinned kernel if it is installed, otherwise the highest eligible version found.
+// Pre-releases are only picked automatically when the policy allows it or nothing
+// else is installed.
+pub fn find_latest_kernel<R: Runtime>(app: &AppHandle<R>) -> Option<(PathBuf, Option<Version>)> {
+    let kernels = scan_kernels(app);
+    let prefs = load_preferences(app);
+
+    if let Some(pinned) = &prefs.pinned_version {
+        if let Some(kernel) = kernels.iter().find(|(_, v)| v.as_ref().is_some_and(|v| &v.to_string() == pinned)) {
+            log::info!("Using pinned kernel {} at {:?}", pinned, kernel.0);
+            return Some(kernel.clone());
+        }
+        log::warn!("Pinned kernel {} is not installed, falling back to the latest one", pinned);
+    }
+
+    let newest = |eligible: &dyn Fn(&Option<Version>) -> bool| {
+        kernels
+            .iter()
+            .filter(|(_, v)| eligible(v))
+            .max_by(|(_, a), (_, b)| compare_versions(a.as_ref(), b.as_ref()))
+            .cloned()
+    };
+
+    let allow_prerelease = prefs.prerelease_allowed();
+    let best = newest(&|v| v.as_ref().is_none_or(|v| allow_prerelease || v.pre.is_empty()));
+    // Only unversioned kernels left, a pre-release is still the better guess
+    if !allow_prerelease && best.as_ref().is_none_or(|(_, v)| v.is_none()) {
+        if let Some(pre) = newest(&|v| v.is_some()) {
+            log::warn!("Only pre-release kernels are installed, using {:?}", pre.1);
+            return Some(pre);
+        }
+    }
+    best
+}
+
+fn preferences_path<R: Runtime>(app: &AppHandle<R>) -> Option<PathBuf> {
+    app.path().app_config_dir().ok().map(|dir| dir.join(PREFERENCES_FILE))
+}
+
+pub fn load_preferences<R: Runtime>(app: &AppHandle<R>) -> KernelPreferences {
+    preferences_path(app)
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_preferences<R: Runtime>(app: &AppHandle<R>, prefs: &KernelPreferences) -> Result<(), String> {
+    let path = preferences_path(app).ok_or("App config dir not available")?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let content = serde_json::to_string_pretty(prefs).map_err(|e| e.to_string())?;
+    std::fs::write(&path, content).map_err(|e| e.to_string())
+}
+
+// Size of a kernel directory, not counting nested directories holding another kernel
+fn kernel_dir_size(dir: &Path, exe_name: &str) -> u64 {
+    let mut total = 0;
+    let mut stack = vec![dir.to_path_buf()];
+    while let Some(current) = stack.pop() {
+        let entries = match std::fs::read_dir(&current) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        for entry in entries.flatten() {
+            let file_type = match entry.file_type() {
+                Ok(t) => t,
+                Err(_) => continue,
+            };
+            let path = entry.path();
+            if file_type.is_dir() {
+                if !path.join(exe_name).is_file() {
+                    stack.push(path);
+                }
+            } else if file_type.is_file() {
+                total += entry.metadata().map(|m| m.len()).unwrap_or(0);
+            }
+        }
+    }
+    total
+}
+
+fn installed_at(path: &Path) -> Option<i64> {
+    let metadata = std::fs::metadata(path).ok()?;
+    let time = metadata.created().or_else(|_| metadata.modified()).ok()?;
+    Some(chrono::DateTime::<chrono::Local>::from(time).timestamp_millis())
+}
+
+pub fn list_installed<R: Runtime>(app: &AppHandle<R>) -> Vec<KernelInfo> {
+    let pinned = load_preferences(app).pinned_version;
+    let active = app
+        .state::<crate::core_manager::CoreState>()
+        .status
+        .lock()
+        .ok()
+        .and_then(|s| s.kernel_path.clone());
+    let exe_name = crate::core_discovery::kernel_exe_name();
+
+    let mut kernels: Vec<KernelInfo> = scan_kernels(app)
+        .into_iter()
+        .map(|(path, version)| {
+            let dir = path.parent().map(|p| p.to_path_buf()).unwrap_or_default();
+            let prerelease = version.as_ref().is_some_and(|v| !v.pre.is_empty());
+            let version = version.map(|v| v.to_string());
+            KernelInfo {
+                path: path.to_string_lossy().to_string(),
+                dir: dir.to_string_lossy().to_string(),
+                prerelease,
+                installed_at: installed_at(&path),
+                size_bytes: kernel_dir_size(&dir, exe_name),
+                pinned: version.is_some() && version == pinned,
+                active: active.as_ref() == Some(&path),
+                version,
+            }
+        })
+        .collect();
+    // Newest first
+    kernels.sort_by(|a, b| {
+        let va = a.version.as_deref().and_then(crate::core_discovery::parse_version);
+        let vb = b.version.as_deref().and_then(crate::core_discovery::parse_version);
+        compare_versions(vb.as_ref(), va.as_ref())
+    });
+    kernels
+}
+
+#[tauri::command]
+pub async fn list_kernels<R: Runtime>(app: AppHandle<R>) -> Result<Vec<KernelInfo>, String> {
+    crate::core_discovery::refresh(&app).await;
+    Ok(list_installed(&app))
+}
+
+// Pin `version` for future starts, or clear the pin when it is None.
+// Takes effect on the next (re)start of the kernel.
+#[tauri::command]
+pub async fn select_kernel<R: Runtime>(app: AppHandle<R>, version: Option<String>) -> Result<Vec<KernelInfo>, String> {
+    crate::core_discovery::refresh(&app).await;
+    let mut prefs = load_preferences(&app);
+    match version.map(|v| v.trim().to_string()).filter(|v| !v.is_empty()) {
+        Some(version) => {
+            let version = crate::core_discovery::parse_version(&version)
+                .map(|v| v.to_string())
+                .ok_or_else(|| format!("Invalid kernel version {}", version))?;
+            let installed = scan_kernels(&app)
+                .into_iter()
+                .any(|(_, v)| v.is_some_and(|v| v.to_string() == version));
+            if !installed {
+                return Err(format!("Kernel version {} is not installed", version));
+            }
+            log::info!("Pinning kernel version {}", version);
+            prefs.pinned_version = Some(version);
+        }
+        None => {
+            log::info!("Clearing kernel version pin");
+            prefs.pinned_version = None;
+        }
+    }
+    save_preferences(&app, &prefs)?;
+    Ok(list_installed(&app))
+}
+
+// Allow or forbid pre-release kernels for automatic selection, None restores the build default.
+// A pinned pre-release is always used regardless of this policy.
+#[tauri::command]
+pub fn set_kernel_prerelease_policy<R: Runtime>(app: AppHandle<R>, allow: Option<bool>) -> Result<Vec<KernelInfo>, String> {
+    let mut prefs = load_preferences(&app);
+    prefs.allow_prerelease = allow;
+    save_preferences(&app, &prefs)?;
+    log::info!("Pre-release kernels eligible for auto-selection: {}", prefs.prerelease_allowed());
+    Ok(list_installed(&app))
+}
@@ -0,0 +1,471 @@
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::process::ExitStatus;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use base64::Engine;
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+use crate::core_readiness::MarkerWatch;
+
+pub const CORE_LOG_EVENT: &str = "core:log";
+
+const LOG_FILE_STEM: &str = "core";
+const LOG_FILE_EXT: &str = "jsonl";
+// Queries read the files backwards in blocks of this size
+const READ_BLOCK: u64 = 64 * 1024;
+
+fn get_log_max_file_bytes() -> u64 {
+    option_env!("VITE_CORE_LOG_MAX_FILE_BYTES")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(5 * 1024 * 1024)
+}
+
+fn get_log_max_files() -> usize {
+    option_env!("VITE_CORE_LOG_MAX_FILES")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(5)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogStream {
+    Stdout,
+    Stderr,
+}
+
+impl LogStream {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LogStream::Stdout => "stdout",
+            LogStream::Stderr => "stderr",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CoreLogPayload {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub ts: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base64: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signal: Option<i32>,
+}
+
+impl CoreLogPayload {
+    fn decoded_text(&self) -> String {
+        if let Some(text) = &self.text {
+            return text.clone();
+        }
+        self.base64
+            .as_ref()
+            .and_then(|b| base64::engine::general_purpose::STANDARD.decode(b).ok())
+            .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+            .unwrap_or_default()
+    }
+
+    // Valid UTF-8 is stored as plain text so it stays greppable on disk,
+    // anything else keeps its raw bytes for the page's GBK fallback.
+    fn for_storage(&self) -> CoreLogPayload {
+        let mut entry = self.clone();
+        if let Some(bytes) = self
+            .base64
+            .as_ref()
+            .and_then(|b| base64::engine::general_purpose::STANDARD.decode(b).ok())
+        {
+            if let Ok(text) = String::from_utf8(bytes) {
+                entry.text = Some(text);
+                entry.base64 = None;
+            }
+        }
+        entry
+    }
+}
+
+struct LogWriter {
+    file: File,
+    size: u64,
+}
+
+// Rotating JSON-lines store: core.jsonl is the active file, core.1.jsonl the
+// previous one and so on up to the configured file count.
+pub struct CoreLogStore {
+    dir: PathBuf,
+    writer: Mutex<Option<LogWriter>>,
+    // Bumped on every rotation so readers can tell their snapshot went stale
+    rotations: AtomicU64,
+}
+
+impl CoreLogStore {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            writer: Mutex::new(None),
+            rotations: AtomicU64::new(0),
+        }
+    }
+
+    fn file_path(&self, index: usize) -> PathBuf {
+        if index == 0 {
+            self.dir.join(format!("{}.{}", LOG_FILE_STEM, LOG_FILE_EXT))
+        } else {
+            self.dir.join(format!("{}.{}.{}", LOG_FILE_STEM, index, LOG_FILE_EXT))
+        }
+    }
+
+    fn open_writer(&self) -> std::io::Result<LogWriter> {
+        std::fs::create_dir_all(&self.dir)?;
+        let path = self.file_path(0);
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+        Ok(LogWriter { file, size })
+    }
+
+    fn rotate(&self) -> std::io::Result<()> {
+        let max_files = get_log_max_files().max(1);
+        let oldest = self.file_path(max_files - 1);
+        if oldest.exists() {
+            std::fs::remove_file(&oldest)?;
+        }
+        for index in (0..max_files - 1).rev() {
+            let from = self.file_path(index);
+            if from.exists() {
+                std::fs::rename(&from, self.file_path(index + 1))?;
+            }
+        }
+        self.rotations.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+
+    // Current length of every log file. Files only grow between rotations, so the
+    // first `len` bytes stay the same while the rotation count doesn't change.
+    fn snapshot(&self) -> (u64, Vec<(usize, u64)>) {
+        let _guard = self.writer.lock();
+        let rotations = self.rotations.load(Ordering::SeqCst);
+        let files = (0..get_log_max_files().max(1))
+            .filter_map(|index| std::fs::metadata(self.file_path(index)).ok().map(|m| (index, m.len())))
+            .collect();
+        (rotations, files)
+    }
+
+    pub fn append(&self, entry: &CoreLogPayload) -> std::io::Result<()> {
+        let mut line = serde_json::to_vec(&entry.for_storage())?;
+        line.push(b'\n');
+
+        let mut guard = self
+            .writer
+            .lock()
+            .map_err(|e| std::io::Error::other(e.to_string()))?;
+
+        if let Some(writer) = guard.as_ref() {
+            if writer.size + line.len() as u64 > get_log_max_file_bytes() {
+                *guard = None;
+                self.rotate()?;
+            }
+        }
+        if guard.is_none() {
+            *guard = Some(self.open_writer()?);
+        }
+        if let Some(writer) = guard.as_mut() {
+            writer.file.write_all(&line)?;
+            writer.size += line.len() as u64;
+        }
+        Ok(())
+    }
+
+    // Returns up to `limit` matching entries newest first. Reads without the writer
+    // lock so the kernel's pipes keep draining, and starts over if a rotation
+    // shifted the files.
+    fn query(&self, limit: usize, matches: &dyn Fn(&CoreLogPayload) -> bool) -> Vec<CoreLogPayload> {
+        let mut items = Vec::new();
+        for _ in 0..3 {
+            let (rotations, files) = self.snapshot();
+            items = self.read_files(&files, limit, matches);
+            if self.rotations.load(Ordering::SeqCst) == rotations {
+                break;
+            }
+        }
+        items
+    }
+
+    // Newest file first, each read from its end, so only as much of the log is
+    // parsed as it takes to collect `limit` entries
+    fn read_files(
+        &self,
+        files: &[(usize, u64)],
+        limit: usize,
+        matches: &dyn Fn(&CoreLogPayload) -> bool,
+    ) -> Vec<CoreLogPayload> {
+        let mut items = Vec::new();
+        for &(index, len) in files {
+            if items.len() >= limit {
+                break;
+            }
+            let file = match File::open(self.file_path(index)) {
+                Ok(f) => f,
+                Err(_) => continue,
+            };
+            let entries = ReverseLines::new(file, len)
+                .filter_map(|line| serde_json::from_slice::<CoreLogPayload>(&line).ok())
+                .filter(|entry| matches(entry))
+                .take(limit - items.len());
+            items.extend(entries);
+        }
+        items
+    }
+}
+
+impl CoreLogStore {
+    // Last `count` output lines, oldest first
+    pub fn tail(&self, count: usize) -> Vec<String> {
+        let mut lines: Vec<String> = self
+            .query(count, &|entry| entry.kind != "exit")
+            .into_iter()
+            .map(|entry| format!("[{}] {}", entry.kind, entry.decoded_text()))
+            .collect();
+        lines.reverse();
+        lines
+    }
+}
+
+// Lines of the first `len` bytes of a file, last line first
+struct ReverseLines {
+    file: File,
+    // Start of the part of the file that hasn't been read yet
+    pos: u64,
+    // Start of the earliest block read so far, may be the tail of a longer line
+    partial: Vec<u8>,
+    // Complete lines of the earliest block, in file order
+    ready: Vec<Vec<u8>>,
+}
+
+impl ReverseLines {
+    fn new(file: File, len: u64) -> Self {
+        Self {
+            file,
+            pos: len,
+            partial: Vec::new(),
+            ready: Vec::new(),
+        }
+    }
+}
+
+impl Iterator for ReverseLines {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Vec<u8>> {
+        loop {
+            if let Some(line) = self.ready.pop() {
+                return Some(line);
+            }
+            if self.pos == 0 {
+                return Some(std::mem::take(&mut self.partial)).filter(|line| !line.is_empty());
+            }
+            let read = READ_BLOCK.min(self.pos);
+            self.pos -= read;
+            let mut block = vec![0; read as usize];
+            self.file.seek(SeekFrom::Start(self.pos)).ok()?;
+            self.file.read_exact(&mut block).ok()?;
+            block.append(&mut self.partial);
+            let mut parts = block.split(|b| *b == b'\n');
+            self.partial = parts.next().unwrap_or_default().to_vec();
+            self.ready = parts.filter(|line| !line.is_empty()).map(|line| line.to_vec()).collect();
+        }
+    }
+}
+
+pub struct LogFilter {
+    pub level: Option<String>,
+    pub start_ts: Option<i64>,
+    pub end_ts: Option<i64>,
+    pub keyword: Option<String>,
+}
+
+impl LogFilter {
+    fn matches(&self, entry: &CoreLogPayload, keyword: Option<&str>) -> bool {
+        if let Some(level) = self.level.as_deref() {
+            if level != "all" && entry.kind != level {
+                return false;
+            }
+        }
+        if self.start_ts.is_some_and(|start| entry.ts < start) {
+            return false;
+        }
+        if self.end_ts.is_some_and(|end| entry.ts > end) {
+            return false;
+        }
+        if let Some(keyword) = keyword {
+            if !entry.decoded_text().to_lowercase().contains(keyword) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+fn now_millis() -> i64 {
+    Local::now().timestamp_millis()
+}
+
+fn publish<R: Runtime>(app: &AppHandle<R>, payload: CoreLogPayload) {
+    if let Some(store) = app.try_state::<CoreLogStore>() {
+        if let Err(e) = store.append(&payload) {
+            log::warn!("Failed to persist kernel log entry: {}", e);
+        }
+    }
+    let _ = app.emit(CORE_LOG_EVENT, payload);
+}
+
+// Forward one pipe of the kernel line by line. Bytes are passed through untouched
+// (the kernel may print GBK on Windows), the log page decodes them itself.
+pub fn pipe_output<R: Runtime, S: Read + Send + 'static>(
+    app: AppHandle<R>,
+    source: S,
+    stream: LogStream,
+    marker: Option<MarkerWatch>,
+) {
+    std::thread::spawn(move || {
+        let mut reader = BufReader::new(source);
+        let mut buf = Vec::with_capacity(1024);
+        loop {
+            buf.clear();
+            match reader.read_until(b'\n', &mut buf) {
+                Ok(0) => break,
+                Ok(_) => {
+                    while matches!(buf.last(), Some(b'\n') | Some(b'\r')) {
+                        buf.pop();
+                    }
+                    if buf.is_empty() {
+                        continue;
+                    }
+                    if let Some(marker) = &marker {
+                        marker.observe(&buf);
+                    }
+                    let payload = CoreLogPayload {
+                        kind: stream.as_str().to_string(),
+                        ts: now_millis(),
+                        text: None,
+                        base64: Some(base64::engine::general_purpose::STANDARD.encode(&buf)),
+                        code: None,
+                        signal: None,
+                    };
+                    publish(&app, payload);
+                }
+                Err(e) => {
+                    log::warn!("Kernel {:?} pipe closed with error: {}", stream, e);
+                    break;
+                }
+            }
+        }
+    });
+}
+
+pub fn emit_exit<R: Runtime>(app: &AppHandle<R>, status: Option<ExitStatus>) {
+    #[cfg(unix)]
+    let signal = {
+        use std::os::unix::process::ExitStatusExt;
+        status.and_then(|s| s.signal())
+    };
+    #[cfg(not(unix))]
+    let signal = None;
+
+    let payload = CoreLogPayload {
+        kind: "exit".to_string(),
+        ts: now_millis(),
+        text: None,
+        base64: None,
+        code: status.and_then(|s| s.code()),
+        signal,
+    };
+    publish(app, payload);
+}
+
+// A remote kernel's output never reaches this machine, ask its log API instead
+async fn get_remote_logs<R: Runtime>(
+    app: &AppHandle<R>,
+    page: u32,
+    size: u32,
+    filter: &LogFilter,
+) -> Result<serde_json::Value, String> {
+    let endpoint = app.state::<crate::core_api::CoreEndpoint>();
+    let client = app.state::<reqwest::Client>();
+    let mut query = vec![("page", page.to_string()), ("size", size.to_string())];
+    if let Some(level) = &filter.level {
+        query.push(("level", level.clone()));
+    }
+    if let Some(start_ts) = filter.start_ts {
+        query.push(("start_ts", start_ts.to_string()));
+    }
+    if let Some(end_ts) = filter.end_ts {
+        query.push(("end_ts", end_ts.to_string()));
+    }
+    if let Some(keyword) = &filter.keyword {
+        query.push(("keyword", keyword.clone()));
+    }
+    let json = endpoint
+        .get(&client, "/api/v1/system/logs")
+        .query(&query)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch remote kernel logs: {}", e))?
+        .error_for_status()
+        .map_err(|e| format!("Failed to fetch remote kernel logs: {}", e))?
+        .json::<serde_json::Value>()
+        .await
+        .map_err(|e| format!("Failed to parse remote kernel logs: {}", e))?;
+    // Unwrap the kernel's {code, data} envelope
+    Ok(json.get("data").cloned().unwrap_or(json))
+}
+
+#[tauri::command]
+pub async fn get_core_logs<R: Runtime>(
+    app: AppHandle<R>,
+    page: u32,
+    size: u32,
+    level: Option<String>,
+    start_ts: Option<i64>,
+    end_ts: Option<i64>,
+    keyword: Option<String>,
+) -> Result<serde_json::Value, String> {
+    let filter = LogFilter {
+        level,
+        start_ts,
+        end_ts,
+        keyword: keyword.filter(|k| !k.is_empty()),
+    };
+    if crate::core_remote::is_remote(&app) {
+        return get_remote_logs(&app, page, size, &filter).await;
+    }
+    let size = size.max(1) as usize;
+    let offset = (page.max(1) as usize - 1) * size;
+    // One entry past the page tells whether there is another one
+    let limit = offset + size + 1;
+    let query_app = app.clone();
+    let items = tauri::async_runtime::spawn_blocking(move || {
+        let keyword = filter.keyword.as_ref().map(|k| k.to_lowercase());
+        query_app
+            .state::<CoreLogStore>()
+            .query(limit, &|entry| filter.matches(entry, keyword.as_deref()))
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    let has_more = items.len() == limit;
+    // Exact once the end of the log was reached, otherwise a lower bound
+    let total = items.len();
+    let page_items: Vec<CoreLogPayload> = items.into_iter().skip(offset).take(size).collect();
+
+    Ok(serde_json::json!({
+        "items": page_items,
+        "total": total,
+        "hasMore": has_more
+    }))
+}
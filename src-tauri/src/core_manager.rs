@@ -1,293 +1,1286 @@
-use std::env;
 use std::path::PathBuf;
-use std::process::{Child, Command as StdCommand};
+use std::process::{Child, Command as StdCommand, Stdio};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Mutex;
-use tauri::{AppHandle, Manager, Runtime};
-use regex::Regex;
-use semver::Version;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+use serde::Serialize;
+
+use crate::core_inventory::{find_latest_kernel, KernelInfo};
+use crate::core_process::ProcessTree;
+use crate::core_readiness::{wait_until_ready, MarkerWatch, ReadinessPlan, ReadinessReport};
+
+pub const CORE_SUPERVISOR_EVENT: &str = "core:supervisor";
+pub const CORE_STATUS_EVENT: &str = "core:status";
+pub const CORE_SHUTDOWN_EVENT: &str = "core:shutdown";
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ShutdownOutcome {
+    NotRunning,
+    // An attached kernel was released without stopping it
+    Detached,
+    // Exited on its own after the shutdown request
+    Clean,
+    // Exited after SIGTERM to the process tree
+    Terminated,
+    Killed,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ShutdownReport {
+    pub outcome: ShutdownOutcome,
+    pub exit_code: Option<i32>,
+    pub elapsed_ms: u64,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ShutdownProgress {
+    // requested | waiting | terminating | done
+    pub stage: String,
+    pub elapsed_ms: u64,
+    pub deadline_ms: u64,
+    pub outcome: Option<ShutdownOutcome>,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CorePhase {
+    #[default]
+    Stopped,
+    Starting,
+    WaitingReady,
+    Ready,
+    // Readiness never came, the process may still be alive
+    Failed,
+    Stopping,
+    Crashed,
+    // Alive but no longer answering health checks
+    Hung,
+    Updating,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CoreMode {
+    // Spawned and owned by the shell
+    #[default]
+    Owned,
+    // Started elsewhere, tracked over HTTP only
+    Attached,
+    // Runs on another machine through the remote profile
+    Remote,
+}
+
+#[derive(Default)]
+pub struct CoreStatusInner {
+    pub phase: CorePhase,
+    pub mode: CoreMode,
+    pub pid: Option<u32>,
+    pub started_at: Option<Instant>,
+    pub kernel_path: Option<PathBuf>,
+    pub version: Option<String>,
+    pub last_error: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CoreStatus {
+    pub phase: CorePhase,
+    pub mode: CoreMode,
+    pub pid: Option<u32>,
+    pub uptime_ms: Option<u64>,
+    pub kernel_path: Option<String>,
+    pub version: Option<String>,
+    pub last_error: Option<String>,
+}
+
+impl CoreStatusInner {
+    fn snapshot(&self) -> CoreStatus {
+        CoreStatus {
+            phase: self.phase,
+            mode: self.mode,
+            pid: self.pid,
+            uptime_ms: self.started_at.map(|t| t.elapsed().as_millis() as u64),
+            kernel_path: self.kernel_path.as_ref().map(|p| p.to_string_lossy().to_string()),
+            version: self.version.clone(),
+            last_error: self.last_error.clone(),
+        }
+    }
+}
 
 pub struct CoreState {
     pub process: Mutex<Option<Child>>,
+    // Process group / job of the running kernel, always locked after `process`
+    pub process_tree: Mutex<Option<ProcessTree>>,
+    pub status: Mutex<CoreStatusInner>,
+    // Base URL of an external kernel we adopted instead of spawning one
+    pub attached: Mutex<Option<String>>,
+    // Set while a stop was requested, so the supervisor doesn't treat it as a crash
+    pub shutdown_requested: AtomicBool,
+    // Times of recent automatic restarts, pruned to the restart window
+    pub restart_history: Mutex<VecDeque<Instant>>,
+    // App exit is held back until the kernel has been shut down
+    pub exiting: AtomicBool,
+    pub exit_ready: AtomicBool,
+    pub restarting: AtomicBool,
+    // When an update was triggered. The kernel exits to apply it, the next
+    // successful start runs the retention policy.
+    pub update_pending: Mutex<Option<Instant>>,
+    // Retention cleanups run one at a time
+    pub cleanup_lock: tokio::sync::Mutex<()>,
 }
 
 impl CoreState {
     pub fn new() -> Self {
         Self {
             process: Mutex::new(None),
+            process_tree: Mutex::new(None),
+            status: Mutex::new(CoreStatusInner::default()),
+            attached: Mutex::new(None),
+            shutdown_requested: AtomicBool::new(false),
+            restart_history: Mutex::new(VecDeque::new()),
+            exiting: AtomicBool::new(false),
+            exit_ready: AtomicBool::new(false),
+            restarting: AtomicBool::new(false),
+            update_pending: Mutex::new(None),
+            cleanup_lock: tokio::sync::Mutex::new(()),
         }
     }
 }
 
-// Helper to get directories to scan
-fn get_scan_dirs<R: Runtime>(app: &AppHandle<R>) -> Vec<PathBuf> {
-    let mut dirs = Vec::new();
-    if let Ok(dir) = env::var("VITE_CORE_INSTALL_DIR") {
-        dirs.push(PathBuf::from(dir));
-    }
-    if let Ok(app_data_dir) = app.path().app_data_dir() {
-        let core_dir = app_data_dir.join("core");
-        dirs.push(core_dir.clone());
-        
-        // Recursively scan all subdirectories in core/
-        let mut stack = vec![core_dir];
-        while let Some(dir) = stack.pop() {
-            if let Ok(entries) = std::fs::read_dir(&dir) {
-                for entry in entries.flatten() {
-                    if let Ok(file_type) = entry.file_type() {
-                        if file_type.is_dir() {
-                            let path = entry.path();
-                            dirs.push(path.clone());
-                            stack.push(path);
-                        }
-                    }
-                }
-            }
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SupervisorEvent {
+    // crashed | updated | restarting | restarted | restart_failed | gave_up
+    pub kind: String,
+    pub pid: Option<u32>,
+    pub code: Option<i32>,
+    pub attempt: usize,
+    pub delay_ms: u64,
+    pub message: Option<String>,
+    pub ts: i64,
+}
+
+fn get_core_shutdown_http_timeout_ms() -> u64 {
+    option_env!("VITE_CORE_SHUTDOWN_HTTP_TIMEOUT_MS")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(2000)
+}
+
+fn get_core_shutdown_wait_ms() -> u64 {
+    option_env!("VITE_CORE_SHUTDOWN_WAIT_MS")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(10000)
+}
+
+fn get_core_terminate_wait_ms() -> u64 {
+    option_env!("VITE_CORE_TERMINATE_WAIT_MS")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(3000)
+}
+
+fn get_core_retain_previous() -> usize {
+    option_env!("VITE_CORE_RETAIN_PREVIOUS")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(2)
+}
+
+// Served by the kernel itself without touching the network or its database,
+// FastAPI's generated schema by default
+fn get_core_identity_path() -> &'static str {
+    option_env!("VITE_CORE_IDENTITY_PATH").unwrap_or("/openapi.json")
+}
+
+// How long a triggered update may take before the kernel exits to apply it
+fn get_core_update_exit_timeout_ms() -> u64 {
+    option_env!("VITE_CORE_UPDATE_EXIT_TIMEOUT_MS")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(120000)
+}
+
+fn get_core_restart_max() -> usize {
+    option_env!("VITE_CORE_RESTART_MAX")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(5)
+}
+
+fn get_core_restart_window_ms() -> u64 {
+    option_env!("VITE_CORE_RESTART_WINDOW_MS")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(10 * 60 * 1000)
+}
+
+fn get_core_restart_backoff_ms() -> u64 {
+    option_env!("VITE_CORE_RESTART_BACKOFF_MS")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(1000)
+}
+
+fn get_core_restart_backoff_max_ms() -> u64 {
+    option_env!("VITE_CORE_RESTART_BACKOFF_MAX_MS")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(30000)
+}
+
+// Apply a change to the lifecycle state and broadcast the result as `core:status`
+pub fn update_status<R: Runtime>(app: &AppHandle<R>, f: impl FnOnce(&mut CoreStatusInner)) {
+    let state = app.state::<CoreState>();
+    let snapshot = match state.status.lock() {
+        Ok(mut status) => {
+            f(&mut status);
+            status.snapshot()
         }
+        Err(_) => return,
+    };
+    let _ = app.emit(CORE_STATUS_EVENT, snapshot);
+}
+
+pub fn set_phase<R: Runtime>(app: &AppHandle<R>, phase: CorePhase) {
+    update_status(app, |status| status.phase = phase);
+}
+
+fn fail_status<R: Runtime>(app: &AppHandle<R>, phase: CorePhase, error: String) {
+    update_status(app, |status| {
+        status.phase = phase;
+        status.last_error = Some(error);
+    });
+}
+
+#[tauri::command]
+pub fn get_core_status(state: tauri::State<'_, CoreState>) -> Result<CoreStatus, String> {
+    let status = state.status.lock().map_err(|e| e.to_string())?;
+    Ok(status.snapshot())
+}
+
+// Start/stop belong to the server in remote mode
+fn ensure_local<R: Runtime>(app: &AppHandle<R>) -> Result<(), String> {
+    if crate::core_remote::is_remote(app) {
+        return Err("The kernel runs in remote mode and is managed on its server".to_string());
     }
-    dirs
+    Ok(())
 }
 
-// Helper to extract version from binary execution
-fn get_binary_version(path: &PathBuf, re: &Regex) -> Option<Version> {
-    let version_file_path = path.with_file_name(format!("{}.version", path.file_name().unwrap_or_default().to_string_lossy()));
-    if version_file_path.exists() {
-        if let Ok(content) = std::fs::read_to_string(&version_file_path) {
-            if let Ok(v) = Version::parse(content.trim()) {
-                return Some(v);
-            }
-        }
+#[tauri::command]
+pub async fn start_core<R: Runtime>(app: AppHandle<R>) -> Result<ReadinessReport, String> {
+    ensure_local(&app)?;
+    launch_core(&app).await
+}
+
+// Shut the kernel down and bring it back up as one operation, then reopen the
+// page its browser was showing before.
+#[tauri::command]
+pub async fn restart_core<R: Runtime>(app: AppHandle<R>) -> Result<ReadinessReport, String> {
+    ensure_local(&app)?;
+    let state = app.state::<CoreState>();
+    if state.attached.lock().map(|a| a.is_some()).unwrap_or(false) {
+        return Err("Cannot restart an attached kernel".to_string());
+    }
+    if state.restarting.swap(true, Ordering::SeqCst) {
+        return Err("A kernel restart is already in progress".to_string());
     }
 
-    #[cfg(target_os = "windows")]
-    use std::os::windows::process::CommandExt;
+    let previous_url = current_browser_url(&app).await;
+    log::info!("Restarting kernel, will restore {:?}", previous_url);
 
-    let mut cmd = StdCommand::new(path);
-    cmd.arg("version");
+    let shutdown_app = app.clone();
+    let shutdown = tauri::async_runtime::spawn_blocking(move || {
+        let state = shutdown_app.state::<CoreState>();
+        shutdown_core_gracefully(&shutdown_app, &state)
+    })
+    .await;
 
-    #[cfg(target_os = "windows")]
-    cmd.creation_flags(0x08000000);
+    let result = match shutdown {
+        Ok(report) => {
+            log::info!("Kernel stopped for restart: {:?}", report.outcome);
+            launch_core_to(&app, previous_url).await
+        }
+        Err(e) => Err(format!("Failed to stop kernel: {}", e)),
+    };
+    state.restarting.store(false, Ordering::SeqCst);
+    result
+}
 
-    match cmd.output() {
-        Ok(output) => {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            if let Some(caps) = re.captures(&stdout) {
-                if let Some(m) = caps.get(1) {
-                    if let Ok(v) = Version::parse(m.as_str()) {
-                        let _ = std::fs::write(&version_file_path, m.as_str());
-                        return Some(v);
-                    }
+// URL the kernel browser currently shows, if it reports one worth restoring
+async fn current_browser_url<R: Runtime>(app: &AppHandle<R>) -> Option<String> {
+    let endpoint = app.state::<crate::core_api::CoreEndpoint>();
+    let client = app.state::<reqwest::Client>();
+    let json = endpoint.get(&client, "/api/v1/browser/status").send().await.ok()?.json::<serde_json::Value>().await.ok()?;
+    let url = json.get("url")?.as_str()?.trim();
+    if url.starts_with("http://") || url.starts_with("https://") {
+        Some(url.to_string())
+    } else {
+        None
+    }
+}
+
+pub async fn launch_core<R: Runtime>(app: &AppHandle<R>) -> Result<ReadinessReport, String> {
+    launch_core_to(app, None).await
+}
+
+// Spawn the kernel (unless it is already running) and wait for the readiness
+// probes. Navigates the kernel browser to `url`, or the startup page, once it is ready.
+async fn launch_core_to<R: Runtime>(app: &AppHandle<R>, url: Option<String>) -> Result<ReadinessReport, String> {
+    ensure_local(app)?;
+    if crate::core_install::is_installing(app) {
+        return Err("The kernel is still being installed".to_string());
+    }
+    let attached = app.state::<CoreState>().attached.lock().ok().and_then(|a| a.clone());
+    if let Some(base) = attached {
+        return attach_external(app, base).await;
+    }
+
+    // Adopt a yuHai kernel that is already listening instead of fighting it for the port.
+    // Anything else there (another install's kernel, a foreign service) is left alone
+    // and our kernel gets a port of its own.
+    let owned = app.state::<CoreState>().process.lock().map(|g| g.is_some()).unwrap_or(false);
+    if !owned {
+        stop_orphan(app).await;
+        let default_base = crate::utils::core_api_base().trim_end_matches('/').to_string();
+        let client = app.state::<reqwest::Client>().inner().clone();
+        match identify_kernel(&client, &default_base, None).await {
+            KernelIdentity::YuHai { version } => {
+                if let Ok(report) = attach_external(app, default_base.clone()).await {
+                    log::info!("Found a running kernel {} at {}, attached to it", version, default_base);
+                    return Ok(report);
                 }
             }
-            None
+            KernelIdentity::Protected => log::warn!(
+                "A kernel started by another launch is listening at {}, it can't be adopted without its token",
+                default_base
+            ),
+            KernelIdentity::Foreign => log::warn!("{} is taken by something that isn't a yuHai kernel", default_base),
+            KernelIdentity::Unresponsive => log::warn!("{} accepts connections but doesn't answer", default_base),
+            KernelIdentity::Absent => {}
         }
-        Err(_) => None,
     }
-}
 
-pub fn has_any_kernel<R: Runtime>(app: &AppHandle<R>) -> bool {
-    let scan_dirs = get_scan_dirs(app);
-    let exe_name = option_env!("VITE_CORE_EXE_NAME")
-        .unwrap_or(if cfg!(target_os = "windows") { "yuHai.exe" } else { "yuHai" });
-    for dir in scan_dirs {
-        let path = dir.join(exe_name);
-        if path.exists() && path.is_file() {
-            return true;
+    crate::core_discovery::refresh(app).await;
+
+    // Refuse to launch a damaged kernel, the frontend offers `repair_kernel` instead
+    let kernel = find_latest_kernel(app);
+    if let Some((kernel_path, _)) = kernel.as_ref().filter(|_| !owned) {
+        set_phase(app, CorePhase::Starting);
+        if let Err(report) = crate::core_integrity::check_kernel(app, kernel_path).await {
+            let error = format!("Kernel integrity check failed: {}", report.summary());
+            fail_status(app, CorePhase::Failed, error.clone());
+            return Err(error);
         }
     }
-    false
-}
 
-pub fn find_latest_kernel<R: Runtime>(app: &AppHandle<R>) -> Option<PathBuf> {
-    let scan_dirs = get_scan_dirs(app);
-    // Compile regex once
-    let re = Regex::new(r"Version:\s*(\d+\.\d+\.\d+)").unwrap();
-    let mut max_version = Version::new(0, 0, 0);
-    let mut best_path: Option<PathBuf> = None;
-    
-    let exe_name = option_env!("VITE_CORE_EXE_NAME")
-        .unwrap_or(if cfg!(target_os = "windows") { "yuHai.exe" } else { "yuHai" });
+    let mut plan = ReadinessPlan::from_env();
+    let (pid, spawned) = {
+        let state = app.state::<CoreState>();
+        spawn_core(app, &state, kernel, plan.marker_watch())?
+    };
+    if !spawned {
+        plan = plan.for_running_kernel();
+    }
+    plan.token = app.state::<crate::core_api::CoreEndpoint>().token();
 
-    log::info!("Searching for kernel executable: {}", exe_name);
+    log::info!("Waiting for core to be ready...");
+    let client = app.state::<reqwest::Client>().inner().clone();
+    let base = app.state::<crate::core_api::CoreEndpoint>().base();
+    let result = wait_until_ready(&client, &base, &plan, || is_current_pid(app, pid)).await;
 
-    for dir in scan_dirs {
-        let path = dir.join(exe_name);
-        if path.exists() && path.is_file() {
-            log::info!("Found kernel at: {:?}", path);
-            if let Some(v) = get_binary_version(&path, &re) {
-                log::info!("Kernel version: {:?}", v);
-                if v > max_version {
-                    max_version = v;
-                    best_path = Some(path);
+    match result {
+        Ok(report) => {
+            if spawned && is_current_pid(app, pid) {
+                set_phase(app, CorePhase::Ready);
+                crate::core_watchdog::start(app.clone(), pid);
+                navigate_to_startup(app, url).await;
+                let updated = app.state::<CoreState>().update_pending.lock().ok().and_then(|mut p| p.take());
+                if updated.is_some() {
+                    schedule_cleanup(app, "Post-update");
                 }
+            }
+            Ok(report)
+        }
+        Err(report) => {
+            let error = if is_current_pid(app, pid) {
+                format!("Kernel did not become ready within {}ms: {}", report.elapsed_ms, report.failure_summary())
             } else {
-                // If we can't get version, but found the file, and haven't found a versioned one yet
-                if best_path.is_none() {
-                    best_path = Some(path);
-                }
+                "Kernel exited before becoming ready".to_string()
+            };
+            log::error!("{}", error);
+            // A kernel this call didn't start keeps its phase, the watchdog judges it
+            if spawned && is_current_pid(app, pid) {
+                fail_status(app, CorePhase::Failed, error.clone());
             }
+            Err(error)
         }
     }
+}
 
-    best_path
+enum KernelIdentity {
+    Absent,
+    YuHai { version: String },
+    // A yuHai kernel that wants the per-launch token of whoever started it
+    Protected,
+    Foreign,
+    // Something holds the port but doesn't answer in time
+    Unresponsive,
 }
 
-fn get_core_ready_retry_count() -> usize {
-    option_env!("VITE_CORE_READY_RETRY_COUNT")
-        .and_then(|v| v.parse().ok())
-        .unwrap_or(30)
+// Ask whatever listens at `base` who it is
+async fn identify_kernel(client: &reqwest::Client, base: &str, token: Option<&str>) -> KernelIdentity {
+    let mut request = client.get(format!("{}{}", base, get_core_identity_path()));
+    if let Some(token) = token {
+        request = request.header(crate::core_api::CORE_TOKEN_HEADER, token);
+    }
+    let response = match request.send().await {
+        Ok(response) => response,
+        Err(e) if e.is_connect() => return KernelIdentity::Absent,
+        Err(e) if e.is_timeout() => return KernelIdentity::Unresponsive,
+        Err(_) => return KernelIdentity::Foreign,
+    };
+    let status = response.status();
+    if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+        return KernelIdentity::Protected;
+    }
+    if !status.is_success() {
+        return KernelIdentity::Foreign;
+    }
+    // {"info": {"version": "1.2.0", …}, "paths": {"/api/v1/system/shutdown": …, …}}
+    let Ok(schema) = response.json::<serde_json::Value>().await else {
+        return KernelIdentity::Foreign;
+    };
+    if schema.pointer("/paths/~1api~1v1~1system~1shutdown").is_none() {
+        return KernelIdentity::Foreign;
+    }
+    let version = schema
+        .pointer("/info/version")
+        .and_then(|v| v.as_str())
+        .unwrap_or("unknown")
+        .to_string();
+    KernelIdentity::YuHai { version }
 }
 
-fn get_core_ready_retry_interval_ms() -> u64 {
-    option_env!("VITE_CORE_READY_RETRY_INTERVAL_MS")
-        .and_then(|v| v.parse().ok())
-        .unwrap_or(2000)
+// A kernel an earlier shell launched and never stopped, usually because that shell
+// crashed. Without its process handle it can't be supervised, so it is shut down
+// with the token it was launched with and a fresh kernel takes its place.
+async fn stop_orphan<R: Runtime>(app: &AppHandle<R>) {
+    let Some(record) = crate::core_api::load_launch_record(app) else {
+        return;
+    };
+    let base = crate::core_api::local_base(record.port);
+    let client = app.state::<reqwest::Client>().inner().clone();
+    if let KernelIdentity::YuHai { version } = identify_kernel(&client, &base, Some(&record.token)).await {
+        log::warn!("Kernel {} (pid {}) from a previous launch is still running at {}, stopping it", version, record.pid, base);
+        let shutdown = client
+            .post(format!("{}/api/v1/system/shutdown", base))
+            .header(crate::core_api::CORE_TOKEN_HEADER, &record.token)
+            .timeout(Duration::from_millis(get_core_shutdown_http_timeout_ms()))
+            .send()
+            .await;
+        if let Err(e) = shutdown {
+            log::warn!("Failed to send shutdown signal to the orphaned kernel: {}", e);
+        }
+        let deadline = Instant::now() + Duration::from_millis(get_core_shutdown_wait_ms());
+        while !crate::core_api::port_is_free(record.port) {
+            if Instant::now() >= deadline {
+                log::warn!("Orphaned kernel {} did not exit in time, killing it", record.pid);
+                if let Err(e) = crate::core_process::kill_orphan(record.pid) {
+                    log::warn!("Failed to kill orphaned kernel: {}", e);
+                }
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(200)).await;
+        }
+    }
+    crate::core_api::clear_launch_record(app);
 }
 
-fn get_core_ready_http_timeout_ms() -> u64 {
-    option_env!("VITE_CORE_READY_HTTP_TIMEOUT_MS")
-        .and_then(|v| v.parse().ok())
-        .unwrap_or(2000)
+// Track a kernel at `base` without owning its process. Fails if it isn't healthy,
+// if it expects a token we don't have, or if the shell is already running a
+// kernel of its own.
+pub async fn attach_external<R: Runtime>(app: &AppHandle<R>, base: String) -> Result<ReadinessReport, String> {
+    let owned = app.state::<CoreState>().process.lock().map(|g| g.is_some()).unwrap_or(false);
+    if owned {
+        return Err("A managed kernel is running, stop it before attaching".to_string());
+    }
+
+    let client = app.state::<reqwest::Client>().inner().clone();
+    let report = wait_until_ready(&client, &base, &ReadinessPlan::single_check(), || false)
+        .await
+        .map_err(|report| format!("No healthy kernel at {}: {}", base, report.failure_summary()))?;
+    if let KernelIdentity::Protected = identify_kernel(&client, &base, None).await {
+        return Err(format!(
+            "The kernel at {} was started with a launch token and can't be attached, stop it or restart the app",
+            base
+        ));
+    }
+
+    let state = app.state::<CoreState>();
+    if let Ok(mut attached) = state.attached.lock() {
+        *attached = Some(base.clone());
+    }
+    let endpoint = app.state::<crate::core_api::CoreEndpoint>();
+    endpoint.set_base(base);
+    endpoint.set_token(None);
+    update_status(app, |status| {
+        let already_attached = status.mode == CoreMode::Attached && status.phase == CorePhase::Ready;
+        status.phase = CorePhase::Ready;
+        status.mode = CoreMode::Attached;
+        status.pid = None;
+        if !already_attached {
+            status.started_at = Some(Instant::now());
+        }
+        status.kernel_path = None;
+        status.version = None;
+        status.last_error = None;
+    });
+    Ok(report)
 }
 
-fn get_core_shutdown_http_timeout_ms() -> u64 {
-    option_env!("VITE_CORE_SHUTDOWN_HTTP_TIMEOUT_MS")
-        .and_then(|v| v.parse().ok())
-        .unwrap_or(2000)
+#[tauri::command]
+pub async fn attach_core<R: Runtime>(app: AppHandle<R>, url: String) -> Result<ReadinessReport, String> {
+    ensure_local(&app)?;
+    let parsed = reqwest::Url::parse(url.trim()).map_err(|e| format!("Invalid kernel URL: {}", e))?;
+    if parsed.scheme() != "http" && parsed.scheme() != "https" {
+        return Err(format!("Unsupported kernel URL scheme: {}", parsed.scheme()));
+    }
+    let base = parsed.as_str().trim_end_matches('/').to_string();
+    log::info!("Attaching to external kernel at {}", base);
+    attach_external(&app, base).await
 }
 
-fn get_core_shutdown_wait_ms() -> u64 {
-    option_env!("VITE_CORE_SHUTDOWN_WAIT_MS")
-        .and_then(|v| v.parse().ok())
-        .unwrap_or(500)
+#[derive(Serialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CleanupReport {
+    pub removed: Vec<KernelInfo>,
+    pub kept: Vec<KernelInfo>,
+    pub bytes_freed: u64,
+    pub errors: Vec<String>,
+}
+
+// Retention policy: keep the active kernel plus the `retain` newest older ones.
+// Pinned, running, newer-than-active and unversioned kernels are never removed,
+// and only directories below app_data/core are ever deleted.
+pub fn cleanup_kernels_with_policy<R: Runtime>(app: &AppHandle<R>, retain: usize) -> CleanupReport {
+    let mut report = CleanupReport::default();
+    let core_dir = match app.path().app_data_dir() {
+        Ok(dir) => dir.join("core"),
+        Err(e) => {
+            report.errors.push(e.to_string());
+            return report;
+        }
+    };
+
+    let kernels = crate::core_inventory::list_installed(app);
+    let active_path = kernels
+        .iter()
+        .find(|k| k.active)
+        .map(|k| PathBuf::from(&k.path))
+        .or_else(|| find_latest_kernel(app).map(|(path, _)| path));
+    let active_version = active_path
+        .as_ref()
+        .and_then(|path| kernels.iter().find(|k| std::path::Path::new(&k.path) == path))
+        .and_then(|k| k.version.as_deref())
+        .and_then(crate::core_discovery::parse_version);
+
+    // `list_installed` is sorted newest first
+    let mut older_kept = 0;
+    let (mut keep, mut remove) = (Vec::new(), Vec::new());
+    for kernel in kernels {
+        let path = PathBuf::from(&kernel.path);
+        let dir = PathBuf::from(&kernel.dir);
+        let version = kernel.version.as_deref().and_then(crate::core_discovery::parse_version);
+        let protected = kernel.pinned
+            || kernel.active
+            || active_path.as_ref() == Some(&path)
+            || version.is_none()
+            || !dir.starts_with(&core_dir)
+            || dir == core_dir;
+        let is_older = match (&version, &active_version) {
+            (Some(v), Some(active)) => v.cmp_precedence(active).is_lt(),
+            _ => false,
+        };
+        if protected || !is_older {
+            keep.push(kernel);
+        } else if older_kept < retain {
+            older_kept += 1;
+            keep.push(kernel);
+        } else {
+            remove.push(kernel);
+        }
+    }
+
+    for kernel in remove {
+        let dir = PathBuf::from(&kernel.dir);
+        // A kept kernel may live in a subdirectory of this one
+        if keep.iter().any(|k| PathBuf::from(&k.path).starts_with(&dir)) {
+            keep.push(kernel);
+            continue;
+        }
+        match std::fs::remove_dir_all(&dir) {
+            Ok(()) => {
+                log::info!("Removed superseded kernel {:?} at {:?}", kernel.version, dir);
+                report.bytes_freed += kernel.size_bytes;
+                report.removed.push(kernel);
+            }
+            Err(e) => {
+                log::warn!("Failed to remove kernel at {:?}: {}", dir, e);
+                report.errors.push(format!("{}: {}", dir.display(), e));
+                keep.push(kernel);
+            }
+        }
+    }
+    report.kept = keep;
+    report
+}
+
+// Apply the retention policy in the background on a fresh discovery scan. Runs at
+// startup, after every install and after the kernel came back from an update.
+pub fn schedule_cleanup<R: Runtime>(app: &AppHandle<R>, reason: &'static str) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<CoreState>();
+        let _guard = state.cleanup_lock.lock().await;
+        crate::core_discovery::refresh(&app).await;
+        let cleanup_app = app.clone();
+        let result = tauri::async_runtime::spawn_blocking(move || {
+            cleanup_kernels_with_policy(&cleanup_app, get_core_retain_previous())
+        })
+        .await;
+        if let Ok(report) = result {
+            log::info!(
+                "{} kernel cleanup removed {} kernel(s), freed {} bytes",
+                reason,
+                report.removed.len(),
+                report.bytes_freed
+            );
+        }
+        crate::core_discovery::refresh(&app).await;
+    });
 }
 
 #[tauri::command]
-pub fn start_core<R: Runtime>(app: AppHandle<R>, state: tauri::State<'_, CoreState>) -> Result<(), String> {
+pub async fn cleanup_kernels<R: Runtime>(app: AppHandle<R>, retain: Option<usize>) -> Result<CleanupReport, String> {
+    let retain = retain.unwrap_or_else(get_core_retain_previous);
+    let state = app.state::<CoreState>();
+    let _guard = state.cleanup_lock.lock().await;
+    crate::core_discovery::refresh(&app).await;
+    let cleanup_app = app.clone();
+    let report = tauri::async_runtime::spawn_blocking(move || cleanup_kernels_with_policy(&cleanup_app, retain))
+        .await
+        .map_err(|e| e.to_string())?;
+    crate::core_discovery::refresh(&app).await;
+    Ok(report)
+}
+
+// Returns the kernel pid and whether it was spawned by this call
+fn spawn_core<R: Runtime>(
+    app: &AppHandle<R>,
+    state: &CoreState,
+    kernel: Option<(PathBuf, Option<semver::Version>)>,
+    marker: Option<MarkerWatch>,
+) -> Result<(u32, bool), String> {
     let mut process_guard = state.process.lock().map_err(|e| e.to_string())?;
 
-    if process_guard.is_some() {
-        return Ok(()); // Already running
+    if let Some(child) = process_guard.as_ref() {
+        return Ok((child.id(), false)); // Already running
     }
+    state.shutdown_requested.store(false, Ordering::SeqCst);
+    set_phase(app, CorePhase::Starting);
 
-    if let Some(kernel_path) = find_latest_kernel(&app) {
+    if let Some((kernel_path, version)) = kernel {
         log::info!("Starting kernel from: {:?}", kernel_path);
         
         // Ensure we execute relative to the directory it resides in, or pass CWD
         let parent_dir = kernel_path.parent().unwrap_or(&kernel_path);
 
+        let profile = crate::core_profile::load_profile(app);
+        if let Err(e) = profile.validate() {
+            let error = format!("Invalid kernel launch profile: {}", e);
+            log::error!("{}", error);
+            fail_status(app, CorePhase::Stopped, error.clone());
+            return Err(error);
+        }
+
+        let port = match crate::core_api::allocate_port() {
+            Ok(port) => port,
+            Err(e) => {
+                let error = format!("Failed to allocate a port for the kernel: {}", e);
+                log::error!("{}", error);
+                fail_status(app, CorePhase::Stopped, error.clone());
+                return Err(error);
+            }
+        };
+        log::info!("Kernel will listen on port {}", port);
+
+        let token = match crate::core_api::generate_token() {
+            Ok(token) => token,
+            Err(e) => {
+                let error = format!("Failed to generate the kernel access token: {}", e);
+                log::error!("{}", error);
+                fail_status(app, CorePhase::Stopped, error.clone());
+                return Err(error);
+            }
+        };
+
         let mut cmd = StdCommand::new(&kernel_path);
-        cmd.arg("start").current_dir(parent_dir);
+        cmd.arg("start");
+        profile.apply(&mut cmd, parent_dir);
+        cmd.env("YUHAI_CORE_HOST", crate::core_api::core_host())
+            .env("YUHAI_CORE_PORT", port.to_string())
+            .env(crate::core_api::CORE_TOKEN_ENV, &token)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
 
         #[cfg(target_os = "windows")]
         {
             use std::os::windows::process::CommandExt;
             cmd.creation_flags(0x08000000);
         }
+        crate::core_process::configure(&mut cmd);
 
         match cmd.spawn() {
-            Ok(child) => {
+            Ok(mut child) => {
+                if let Some(nice) = profile.nice {
+                    if let Err(e) = crate::core_process::set_priority(&child, nice) {
+                        log::warn!("Failed to set kernel priority to {}: {}", nice, e);
+                    }
+                }
+                match ProcessTree::attach(&child) {
+                    Ok(tree) => {
+                        if let Ok(mut tree_guard) = state.process_tree.lock() {
+                            *tree_guard = Some(tree);
+                        }
+                    }
+                    Err(e) => log::warn!("Failed to track kernel process tree: {}", e),
+                }
+                if let Some(stdout) = child.stdout.take() {
+                    crate::core_log::pipe_output(app.clone(), stdout, crate::core_log::LogStream::Stdout, marker.clone());
+                }
+                if let Some(stderr) = child.stderr.take() {
+                    crate::core_log::pipe_output(app.clone(), stderr, crate::core_log::LogStream::Stderr, marker);
+                }
+                let pid = child.id();
                 *process_guard = Some(child);
-                log::info!("Kernel started successfully.");
-                
-                // Spawn a thread to wait for core readiness and navigate
-                wait_for_core_and_navigate();
-                
-                Ok(())
+                let record = crate::core_api::LaunchRecord { pid, port, token: token.clone() };
+                if let Err(e) = crate::core_api::save_launch_record(app, &record) {
+                    log::warn!("Failed to record the kernel launch: {}", e);
+                }
+                let endpoint = app.state::<crate::core_api::CoreEndpoint>();
+                endpoint.set_base(crate::core_api::local_base(port));
+                endpoint.set_token(Some(token));
+                log::info!("Kernel started successfully (pid {}).", pid);
+                update_status(app, |status| {
+                    status.phase = CorePhase::WaitingReady;
+                    status.mode = CoreMode::Owned;
+                    status.pid = Some(pid);
+                    status.started_at = Some(Instant::now());
+                    status.kernel_path = Some(kernel_path.clone());
+                    status.version = version.map(|v| v.to_string());
+                    status.last_error = None;
+                });
+
+                watch_core_exit(app.clone(), pid);
+                crate::core_metrics::start_sampler(app.clone(), pid);
+
+                Ok((pid, true))
             }
             Err(e) => {
                 log::error!("Failed to start kernel: {}", e);
-                Err(format!("Failed to start kernel: {}", e))
+                let error = format!("Failed to start kernel: {}", e);
+                fail_status(app, CorePhase::Stopped, error.clone());
+                Err(error)
             }
         }
     } else {
         log::error!("Kernel executable not found");
+        fail_status(app, CorePhase::Stopped, "Kernel executable not found".to_string());
         Err("Kernel executable not found".to_string())
     }
 }
 
+// Runs on a blocking worker so the window keeps rendering shutdown progress
 #[tauri::command]
-pub fn stop_core(state: tauri::State<'_, CoreState>) -> Result<(), String> {
-    shutdown_core_gracefully(&state);
-    Ok(())
+pub async fn stop_core<R: Runtime>(app: AppHandle<R>) -> Result<ShutdownReport, String> {
+    ensure_local(&app)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<CoreState>();
+        shutdown_core_gracefully(&app, &state)
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+fn emit_supervisor_event<R: Runtime>(app: &AppHandle<R>, event: SupervisorEvent) {
+    let _ = app.emit(CORE_SUPERVISOR_EVENT, event);
+}
+
+// Poll the child until it exits and report the exit code to the log page.
+// Stops silently if the process was reaped elsewhere (shutdown reports it itself).
+fn watch_core_exit<R: Runtime>(app: AppHandle<R>, pid: u32) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(std::time::Duration::from_millis(500));
+        let state = app.state::<CoreState>();
+        // A running shutdown reaps the process and reports the outcome itself
+        if state.shutdown_requested.load(Ordering::SeqCst) && state.process.lock().map(|g| g.is_some()).unwrap_or(false) {
+            continue;
+        }
+        let mut process_guard = match state.process.lock() {
+            Ok(guard) => guard,
+            Err(_) => return,
+        };
+        let status = match process_guard.as_mut() {
+            Some(child) if child.id() == pid => match child.try_wait() {
+                Ok(Some(status)) => status,
+                Ok(None) => continue,
+                Err(e) => {
+                    log::error!("Failed to poll kernel process: {}", e);
+                    return;
+                }
+            },
+            _ => return,
+        };
+        process_guard.take();
+        let tree = state.process_tree.lock().ok().and_then(|mut t| t.take());
+        drop(process_guard);
+        crate::core_api::clear_launch_record(&app);
+        log::warn!("Kernel process {} exited: {}", pid, status);
+
+        // Don't leave the kernel's browsers running without it
+        if let Some(tree) = tree {
+            if let Err(e) = tree.kill() {
+                log::warn!("Failed to clean up kernel process tree: {}", e);
+            }
+        }
+        crate::core_log::emit_exit(&app, Some(status));
+
+        if state.shutdown_requested.load(Ordering::SeqCst) {
+            update_status(&app, |s| {
+                s.phase = CorePhase::Stopped;
+                s.pid = None;
+                s.started_at = None;
+            });
+        } else if update_in_flight(&state) {
+            // The kernel exits on its own to apply an update, bring up the new version
+            update_status(&app, |s| {
+                s.phase = CorePhase::Updating;
+                s.pid = None;
+                s.started_at = None;
+            });
+            supervise_restart(&app, pid, status.code(), ExitCause::Update);
+        } else {
+            update_status(&app, |s| {
+                s.phase = CorePhase::Crashed;
+                s.pid = None;
+                s.started_at = None;
+                s.last_error = Some(format!("Kernel exited unexpectedly: {}", status));
+            });
+            supervise_restart(&app, pid, status.code(), ExitCause::Crash);
+        }
+        return;
+    });
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ExitCause {
+    Crash,
+    Update,
+}
+
+// An update was triggered recently enough that a kernel exit is expected
+fn update_in_flight(state: &CoreState) -> bool {
+    let window = Duration::from_millis(get_core_update_exit_timeout_ms());
+    state
+        .update_pending
+        .lock()
+        .ok()
+        .and_then(|p| *p)
+        .is_some_and(|at| at.elapsed() < window)
+}
+
+// The kernel accepted an update and will exit to apply it. If it is still the
+// same process once the timeout passes, the update is written off so the phase
+// doesn't stay `Updating` and a later crash isn't taken for an update.
+pub fn mark_update_pending<R: Runtime>(app: &AppHandle<R>) {
+    let state = app.state::<CoreState>();
+    let pid = state.status.lock().ok().and_then(|s| s.pid);
+    let at = Instant::now();
+    if let Ok(mut pending) = state.update_pending.lock() {
+        *pending = Some(at);
+    }
+    set_phase(app, CorePhase::Updating);
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(get_core_update_exit_timeout_ms()));
+        let state = app.state::<CoreState>();
+        let same_process = pid.is_some() && state.status.lock().ok().and_then(|s| s.pid) == pid;
+        let expired = match state.update_pending.lock() {
+            Ok(mut pending) if same_process && *pending == Some(at) => {
+                *pending = None;
+                true
+            }
+            _ => false,
+        };
+        if expired {
+            log::warn!(
+                "Kernel did not restart within {}ms of the update, keeping the running version",
+                get_core_update_exit_timeout_ms()
+            );
+            update_status(&app, |s| {
+                if s.phase == CorePhase::Updating {
+                    s.phase = CorePhase::Ready;
+                }
+            });
+        }
+    });
 }
 
-pub fn shutdown_core_gracefully(state: &CoreState) {
-    let shutdown_url = format!("{}/api/v1/system/shutdown", crate::utils::core_api_base());
+// Restart a crashed kernel with exponential backoff, retrying failed attempts until
+// one succeeds. Gives up once more than VITE_CORE_RESTART_MAX restarts happened
+// within VITE_CORE_RESTART_WINDOW_MS. A kernel that exited to apply an update goes
+// through the same budget.
+fn supervise_restart<R: Runtime>(app: &AppHandle<R>, pid: u32, code: Option<i32>, cause: ExitCause) {
+    let state = app.state::<CoreState>();
+    let window = Duration::from_millis(get_core_restart_window_ms());
+
+    let kind = match cause {
+        ExitCause::Crash => {
+            log::error!("Kernel process {} crashed (code {:?})", pid, code);
+            "crashed"
+        }
+        ExitCause::Update => {
+            log::info!("Kernel process {} exited to apply an update, starting the updated kernel", pid);
+            "updated"
+        }
+    };
+    emit_supervisor_event(app, SupervisorEvent {
+        kind: kind.to_string(),
+        pid: Some(pid),
+        code,
+        attempt: 0,
+        delay_ms: 0,
+        message: None,
+        ts: chrono::Local::now().timestamp_millis(),
+    });
+
+    loop {
+        let attempt = {
+            let mut history = match state.restart_history.lock() {
+                Ok(h) => h,
+                Err(_) => return,
+            };
+            while history.front().is_some_and(|t| t.elapsed() > window) {
+                history.pop_front();
+            }
+            history.len() + 1
+        };
+
+        if attempt > get_core_restart_max() {
+            log::error!("Kernel crashed too often, giving up on automatic restart.");
+            if let Ok(mut pending) = state.update_pending.lock() {
+                *pending = None;
+            }
+            emit_supervisor_event(app, SupervisorEvent {
+                kind: "gave_up".to_string(),
+                pid: Some(pid),
+                code,
+                attempt,
+                delay_ms: 0,
+                message: Some(format!("More than {} restarts within {}s", get_core_restart_max(), window.as_secs())),
+                ts: chrono::Local::now().timestamp_millis(),
+            });
+            return;
+        }
+
+        let delay_ms = get_core_restart_backoff_ms()
+            .saturating_mul(1u64 << (attempt - 1).min(16))
+            .min(get_core_restart_backoff_max_ms());
+        log::info!("Kernel restart attempt {} in {}ms", attempt, delay_ms);
+        emit_supervisor_event(app, SupervisorEvent {
+            kind: "restarting".to_string(),
+            pid: Some(pid),
+            code,
+            attempt,
+            delay_ms,
+            message: None,
+            ts: chrono::Local::now().timestamp_millis(),
+        });
+        std::thread::sleep(Duration::from_millis(delay_ms));
+
+        // The user may have stopped the kernel or switched to a remote one while we were waiting
+        if state.shutdown_requested.load(Ordering::SeqCst) || crate::core_remote::is_remote(app) {
+            log::info!("Kernel stop requested during backoff, skipping restart.");
+            return;
+        }
+        if let Ok(mut history) = state.restart_history.lock() {
+            history.push_back(Instant::now());
+        }
+
+        let result = tauri::async_runtime::block_on(launch_core(app));
+        let new_pid = state.process.lock().ok().and_then(|g| g.as_ref().map(|c| c.id()));
+        match result {
+            Ok(_) => {
+                emit_supervisor_event(app, SupervisorEvent {
+                    kind: "restarted".to_string(),
+                    pid: new_pid,
+                    code: None,
+                    attempt,
+                    delay_ms,
+                    message: None,
+                    ts: chrono::Local::now().timestamp_millis(),
+                });
+                return;
+            }
+            Err(e) => {
+                log::error!("Automatic kernel restart failed: {}", e);
+                emit_supervisor_event(app, SupervisorEvent {
+                    kind: "restart_failed".to_string(),
+                    pid: new_pid,
+                    code: None,
+                    attempt,
+                    delay_ms,
+                    message: Some(e),
+                    ts: chrono::Local::now().timestamp_millis(),
+                });
+                if state.shutdown_requested.load(Ordering::SeqCst) {
+                    return;
+                }
+                // A kernel that never became ready is stopped before the next attempt,
+                // without its exit counting as another crash
+                if new_pid.is_some() {
+                    let report = shutdown_core_gracefully(app, &state);
+                    log::info!("Stopped kernel that failed to start: {:?}", report.outcome);
+                    state.shutdown_requested.store(false, Ordering::SeqCst);
+                }
+            }
+        }
+    }
+}
+
+fn emit_shutdown_progress<R: Runtime>(app: &AppHandle<R>, stage: &str, started: Instant, outcome: Option<ShutdownOutcome>) {
+    let _ = app.emit(CORE_SHUTDOWN_EVENT, ShutdownProgress {
+        stage: stage.to_string(),
+        elapsed_ms: started.elapsed().as_millis() as u64,
+        deadline_ms: get_core_shutdown_wait_ms(),
+        outcome,
+    });
+}
+
+// Ask the kernel to exit over HTTP and poll until it does. Past the deadline the
+// process tree is terminated and finally killed. Progress is emitted as `core:shutdown`.
+pub fn shutdown_core_gracefully<R: Runtime>(app: &AppHandle<R>, state: &CoreState) -> ShutdownReport {
+    state.shutdown_requested.store(true, Ordering::SeqCst);
+    let started = Instant::now();
+
+    // We don't own an attached kernel, just stop tracking it
+    let attached = state.attached.lock().ok().and_then(|mut a| a.take());
+    if let Some(base) = attached {
+        log::info!("Detached from external kernel at {}", base);
+        update_status(app, |s| {
+            s.phase = CorePhase::Stopped;
+            s.mode = CoreMode::Owned;
+            s.started_at = None;
+        });
+        return ShutdownReport {
+            outcome: ShutdownOutcome::Detached,
+            exit_code: None,
+            elapsed_ms: 0,
+        };
+    }
+
+    let running = state.process.lock().map(|g| g.is_some()).unwrap_or(false);
+    if !running {
+        set_phase(app, CorePhase::Stopped);
+        return ShutdownReport {
+            outcome: ShutdownOutcome::NotRunning,
+            exit_code: None,
+            elapsed_ms: 0,
+        };
+    }
+
+    set_phase(app, CorePhase::Stopping);
+    emit_shutdown_progress(app, "requested", started, None);
+
+    let endpoint = app.state::<crate::core_api::CoreEndpoint>();
+    let shutdown_url = endpoint.url("/api/v1/system/shutdown");
 
     let client = reqwest::blocking::Client::builder()
         .no_proxy()
         .connect_timeout(std::time::Duration::from_millis(500))
         .build()
         .unwrap_or_else(|_| reqwest::blocking::Client::new());
-    match client.post(&shutdown_url)
+    match endpoint.authorize_blocking(client.post(&shutdown_url))
         .timeout(std::time::Duration::from_millis(get_core_shutdown_http_timeout_ms()))
         .send() {
         Ok(_) => log::info!("Sent shutdown signal to core."),
         Err(e) => log::error!("Failed to send shutdown signal: {}", e),
     }
 
-    std::thread::sleep(std::time::Duration::from_millis(get_core_shutdown_wait_ms()));
+    // Poll for a clean exit until the deadline
+    let deadline = Duration::from_millis(get_core_shutdown_wait_ms());
+    let mut last_progress = Instant::now();
+    while started.elapsed() < deadline {
+        let exited = match state.process.lock() {
+            Ok(mut guard) => match guard.as_mut() {
+                Some(child) => !matches!(child.try_wait(), Ok(None)),
+                None => true,
+            },
+            Err(_) => true,
+        };
+        if exited {
+            break;
+        }
+        if last_progress.elapsed() >= Duration::from_millis(500) {
+            emit_shutdown_progress(app, "waiting", started, None);
+            last_progress = Instant::now();
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
 
+    let mut outcome = ShutdownOutcome::NotRunning;
+    let mut exit_status = None;
     if let Ok(mut process_guard) = state.process.lock() {
+        let tree = state.process_tree.lock().ok().and_then(|mut t| t.take());
         if let Some(mut child) = process_guard.take() {
             match child.try_wait() {
-                Ok(Some(_)) => log::info!("Core process exited gracefully."),
-                Ok(None) => {
-                    log::warn!("Core process still running, force killing...");
-                    let _ = child.kill();
+                Ok(Some(status)) => {
+                    log::info!("Core process exited gracefully after {}ms ({}).", started.elapsed().as_millis(), status);
+                    outcome = ShutdownOutcome::Clean;
+                    exit_status = Some(status);
                 },
-                Err(_) => {
-                    let _ = child.kill();
-                }
+                _ => {
+                    log::warn!("Core process still running after {}ms, terminating process tree...", deadline.as_millis());
+                    emit_shutdown_progress(app, "terminating", started, None);
+                    let (status, forced) = terminate_tree(&mut child, tree.as_ref());
+                    outcome = forced;
+                    exit_status = status;
+                    log::warn!("Core process was stopped forcibly ({:?}).", outcome);
+                },
+            }
+            crate::core_log::emit_exit(app, exit_status);
+            crate::core_api::clear_launch_record(app);
+        }
+        // Reap anything the kernel left behind, even after a clean exit
+        if let Some(tree) = tree {
+            if let Err(e) = tree.kill() {
+                log::warn!("Failed to kill kernel process tree: {}", e);
             }
         }
     }
-}
+    update_status(app, |s| {
+        s.phase = CorePhase::Stopped;
+        s.pid = None;
+        s.started_at = None;
+    });
+    emit_shutdown_progress(app, "done", started, Some(outcome));
 
-fn wait_for_core_and_navigate() {
-    std::thread::spawn(|| {
-        let navigate_url = format!("{}/api/v1/browser/navigate", crate::utils::core_api_base());
-        let check_url = format!("{}/docs", crate::utils::core_api_base());
+    ShutdownReport {
+        outcome,
+        exit_code: exit_status.and_then(|s| s.code()),
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    }
+}
 
-        let client = reqwest::blocking::Client::builder()
-            .no_proxy()
-            .connect_timeout(std::time::Duration::from_millis(500))
-            .build()
-            .unwrap_or_else(|_| reqwest::blocking::Client::new());
-        let max_retries = get_core_ready_retry_count();
-        let timeout = std::time::Duration::from_millis(get_core_ready_http_timeout_ms());
-        let retry_interval = std::time::Duration::from_millis(get_core_ready_retry_interval_ms());
-        let mut ready = false;
+// Called on `ExitRequested`. Returns true if exit must be prevented: the kernel is
+// shut down on a background thread and the app exits again once it is down.
+pub fn shutdown_before_exit<R: Runtime>(app: &AppHandle<R>, code: Option<i32>) -> bool {
+    let state = app.state::<CoreState>();
+    if state.exit_ready.load(Ordering::SeqCst) {
+        return false;
+    }
+    let running = state.process.lock().map(|g| g.is_some()).unwrap_or(false);
+    if !running {
+        return false;
+    }
+    if state.exiting.swap(true, Ordering::SeqCst) {
+        return true; // Already shutting down
+    }
 
-        log::info!("Waiting for core to be ready...");
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let state = app.state::<CoreState>();
+        let report = shutdown_core_gracefully(&app, &state);
+        log::info!("Kernel shut down before exit: {:?}", report.outcome);
+        state.exit_ready.store(true, Ordering::SeqCst);
+        app.exit(code.unwrap_or(0));
+    });
+    true
+}
 
-        for _ in 0..max_retries {
-            if client.get(&check_url).timeout(timeout).send().is_ok() {
-                ready = true;
-                break;
+// SIGTERM the whole tree, give it a moment, then SIGKILL it
+fn terminate_tree(child: &mut Child, tree: Option<&ProcessTree>) -> (Option<std::process::ExitStatus>, ShutdownOutcome) {
+    if let Some(tree) = tree {
+        if let Err(e) = tree.terminate() {
+            log::warn!("Failed to terminate kernel process tree: {}", e);
+        }
+        let deadline = Instant::now() + Duration::from_millis(get_core_terminate_wait_ms());
+        while Instant::now() < deadline {
+            if let Ok(Some(status)) = child.try_wait() {
+                return (Some(status), ShutdownOutcome::Terminated);
             }
-            std::thread::sleep(retry_interval);
-        }
-
-        if ready {
-             let startup_url = option_env!("VITE_CORE_STARTUP_URL").unwrap_or("https://www.xiaohongshu.com");
-             log::info!("Core is ready. Navigating to {}...", startup_url);
-             match client.post(&navigate_url)
-                .json(&serde_json::json!({
-                    "url": startup_url
-                }))
-                .send() {
-                Ok(resp) => {
-                     if resp.status().is_success() {
-                         log::info!("Successfully navigated to Xiaohongshu");
-                     } else {
-                         log::error!("Failed to navigate: {:?}", resp.text().ok());
-                     }
-                },
-                Err(e) => log::error!("Failed to call navigate endpoint: {}", e),
-             }
-        } else {
-            log::error!("Core startup timed out - could not navigate.");
+            std::thread::sleep(Duration::from_millis(100));
         }
+        log::warn!("Kernel process tree did not exit in time, killing...");
+        if let Err(e) = tree.kill() {
+            log::warn!("Failed to kill kernel process tree: {}", e);
+        }
+    }
+    let _ = child.kill();
+    (child.wait().ok(), ShutdownOutcome::Killed)
+}
+
+// Only report readiness for the process we were started for, a restart may have replaced it
+pub fn is_current_pid<R: Runtime>(app: &AppHandle<R>, pid: u32) -> bool {
+    let state = app.state::<CoreState>();
+    let current = state.status.lock().ok().and_then(|s| s.pid);
+    current == Some(pid)
+}
+
+// Kill the tree of an unresponsive kernel. Leaves `shutdown_requested` alone so the
+// exit watcher treats it as a crash and the supervisor restarts it.
+pub fn kill_hung_kernel<R: Runtime>(app: &AppHandle<R>, pid: u32) {
+    let state = app.state::<CoreState>();
+    let mut process = match state.process.lock() {
+        Ok(p) => p,
+        Err(_) => return,
+    };
+    let child = match process.as_mut() {
+        Some(child) if child.id() == pid => child,
+        _ => return,
+    };
+    log::warn!("Killing hung kernel {}", pid);
+    let tree = state.process_tree.lock().ok();
+    let result = match tree.as_ref().and_then(|t| t.as_ref()) {
+        Some(tree) => tree.kill(),
+        // No job object or process group, at least take down the kernel itself
+        None => child.kill(),
+    };
+    if let Err(e) = result {
+        log::error!("Failed to kill hung kernel: {}", e);
+    }
+}
+
+async fn navigate_to_startup<R: Runtime>(app: &AppHandle<R>, url: Option<String>) {
+    let endpoint = app.state::<crate::core_api::CoreEndpoint>();
+    let client = app.state::<reqwest::Client>();
+    let startup_url = url.unwrap_or_else(|| {
+        option_env!("VITE_CORE_STARTUP_URL").unwrap_or("https://www.xiaohongshu.com").to_string()
     });
+    log::info!("Core is ready. Navigating to {}...", startup_url);
+    match endpoint.post(&client, "/api/v1/browser/navigate")
+        .json(&serde_json::json!({
+            "url": startup_url
+        }))
+        .send()
+        .await {
+        Ok(resp) => {
+            if resp.status().is_success() {
+                log::info!("Successfully navigated to {}", startup_url);
+            } else {
+                log::error!("Failed to navigate: {:?}", resp.text().await.ok());
+            }
+        },
+        Err(e) => log::error!("Failed to call navigate endpoint: {}", e),
+    }
 }
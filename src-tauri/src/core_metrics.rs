@@ -0,0 +1,274 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+pub const CORE_METRICS_EVENT: &str = "core:metrics";
+
+fn get_core_metrics_interval_ms() -> u64 {
+    option_env!("VITE_CORE_METRICS_INTERVAL_MS")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(5000)
+}
+
+fn get_core_metrics_history() -> usize {
+    option_env!("VITE_CORE_METRICS_HISTORY")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(120)
+}
+
+// Thresholds, 0 disables them
+fn get_core_metrics_rss_warn_mb() -> u64 {
+    option_env!("VITE_CORE_METRICS_RSS_WARN_MB")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0)
+}
+
+fn get_core_metrics_rss_restart_mb() -> u64 {
+    option_env!("VITE_CORE_METRICS_RSS_RESTART_MB")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0)
+}
+
+fn get_core_metrics_cpu_warn_percent() -> f64 {
+    option_env!("VITE_CORE_METRICS_CPU_WARN_PERCENT")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0.0)
+}
+
+// Resource usage of the kernel and every process below it
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CoreMetrics {
+    pub ts: i64,
+    pub pid: u32,
+    // Summed over the tree, so it can exceed 100 on multi-core machines
+    pub cpu_percent: f64,
+    pub rss_bytes: u64,
+    pub threads: u64,
+    pub children: u32,
+    pub alerts: Vec<String>,
+    // Memory is over the restart threshold, the kernel restarts once no task is running
+    pub restart_pending: bool,
+}
+
+pub struct MetricsState {
+    history: Mutex<VecDeque<CoreMetrics>>,
+}
+
+impl MetricsState {
+    pub fn new() -> Self {
+        Self {
+            history: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    fn push(&self, metrics: CoreMetrics) {
+        if let Ok(mut history) = self.history.lock() {
+            history.push_back(metrics);
+            while history.len() > get_core_metrics_history() {
+                history.pop_front();
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct TreeSample {
+    // utime + stime in clock ticks
+    cpu_ticks: u64,
+    rss_bytes: u64,
+    threads: u64,
+    children: u32,
+}
+
+#[cfg(target_os = "linux")]
+mod procfs {
+    use std::collections::HashMap;
+
+    use super::TreeSample;
+
+    struct ProcStat {
+        ppid: u32,
+        cpu_ticks: u64,
+        threads: u64,
+        rss_pages: u64,
+    }
+
+    // /proc/<pid>/stat, the command name is in parentheses and may contain spaces
+    fn read_stat(pid: u32) -> Option<ProcStat> {
+        let content = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+        let rest = &content[content.rfind(')')? + 2..];
+        // Fields after the name, starting at field 3 (state)
+        let fields: Vec<&str> = rest.split_whitespace().collect();
+        let field = |n: usize| fields.get(n - 3).and_then(|v| v.parse::<u64>().ok());
+        Some(ProcStat {
+            ppid: field(4)? as u32,
+            cpu_ticks: field(14)? + field(15)?,
+            threads: field(20)?,
+            rss_pages: field(24)?,
+        })
+    }
+
+    pub fn clock_ticks_per_sec() -> u64 {
+        let ticks = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+        if ticks > 0 {
+            ticks as u64
+        } else {
+            100
+        }
+    }
+
+    pub fn sample_tree(root: u32) -> Option<TreeSample> {
+        let mut stats: HashMap<u32, ProcStat> = HashMap::new();
+        for entry in std::fs::read_dir("/proc").ok()?.flatten() {
+            if let Some(pid) = entry.file_name().to_str().and_then(|n| n.parse::<u32>().ok()) {
+                if let Some(stat) = read_stat(pid) {
+                    stats.insert(pid, stat);
+                }
+            }
+        }
+        stats.get(&root)?;
+
+        let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) }.max(0) as u64;
+        let mut sample = TreeSample::default();
+        let mut queue = vec![root];
+        while let Some(pid) = queue.pop() {
+            let Some(stat) = stats.get(&pid) else {
+                continue;
+            };
+            sample.cpu_ticks += stat.cpu_ticks;
+            sample.rss_bytes += stat.rss_pages * page_size;
+            sample.threads += stat.threads;
+            if pid != root {
+                sample.children += 1;
+            }
+            queue.extend(stats.iter().filter(|(_, s)| s.ppid == pid).map(|(child, _)| *child));
+        }
+        Some(sample)
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn sample_tree(pid: u32) -> Option<TreeSample> {
+    procfs::sample_tree(pid)
+}
+
+#[cfg(target_os = "linux")]
+fn clock_ticks_per_sec() -> u64 {
+    procfs::clock_ticks_per_sec()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn sample_tree(_pid: u32) -> Option<TreeSample> {
+    None
+}
+
+#[cfg(not(target_os = "linux"))]
+fn clock_ticks_per_sec() -> u64 {
+    100
+}
+
+// Whether the kernel reports no pending or running task, None if it can't be asked
+async fn kernel_is_idle<R: Runtime>(app: &AppHandle<R>) -> Option<bool> {
+    let client = app.state::<reqwest::Client>().inner().clone();
+    let endpoint = app.state::<crate::core_api::CoreEndpoint>();
+    let json = endpoint.get(&client, "/api/v1/tasks/").send().await.ok()?.json::<serde_json::Value>().await.ok()?;
+    let data = json.get("data").unwrap_or(&json);
+    let tasks = data.as_array().or_else(|| data.get("items").and_then(|i| i.as_array()))?;
+    Some(!tasks.iter().any(|task| {
+        matches!(task.get("status").and_then(|s| s.as_str()), Some("pending" | "running" | "paused"))
+    }))
+}
+
+// Sample the kernel tree until `pid` is no longer the current kernel
+pub fn start_sampler<R: Runtime>(app: AppHandle<R>, pid: u32) {
+    std::thread::spawn(move || {
+        let interval = Duration::from_millis(get_core_metrics_interval_ms().max(500));
+        let ticks_per_sec = clock_ticks_per_sec() as f64;
+        let rss_warn = get_core_metrics_rss_warn_mb() * 1024 * 1024;
+        let rss_restart = get_core_metrics_rss_restart_mb() * 1024 * 1024;
+        let cpu_warn = get_core_metrics_cpu_warn_percent();
+
+        let mut previous: Option<(Instant, u64)> = None;
+        let mut warned = false;
+        let mut restart_pending = false;
+        loop {
+            std::thread::sleep(interval);
+            if !crate::core_manager::is_current_pid(&app, pid) {
+                break;
+            }
+            let Some(sample) = sample_tree(pid) else {
+                if previous.is_none() {
+                    log::info!("Kernel resource telemetry is not available on this platform");
+                    break;
+                }
+                continue;
+            };
+
+            let now = Instant::now();
+            let cpu_percent = match previous {
+                Some((at, ticks)) => {
+                    let elapsed = now.duration_since(at).as_secs_f64();
+                    let used = sample.cpu_ticks.saturating_sub(ticks) as f64 / ticks_per_sec;
+                    if elapsed > 0.0 { used / elapsed * 100.0 } else { 0.0 }
+                }
+                None => 0.0,
+            };
+            previous = Some((now, sample.cpu_ticks));
+
+            let mut alerts = Vec::new();
+            if rss_warn > 0 && sample.rss_bytes >= rss_warn {
+                alerts.push(format!("Memory usage is {} MB", sample.rss_bytes / 1024 / 1024));
+            }
+            if cpu_warn > 0.0 && cpu_percent >= cpu_warn {
+                alerts.push(format!("CPU usage is {:.0}%", cpu_percent));
+            }
+            if rss_restart > 0 && sample.rss_bytes >= rss_restart && !restart_pending {
+                log::warn!(
+                    "Kernel memory usage {} MB is over the restart threshold, restarting once idle",
+                    sample.rss_bytes / 1024 / 1024
+                );
+                restart_pending = true;
+            }
+            if !alerts.is_empty() && !warned {
+                log::warn!("Kernel resource usage: {}", alerts.join(", "));
+            }
+            warned = !alerts.is_empty();
+
+            let metrics = CoreMetrics {
+                ts: chrono::Local::now().timestamp_millis(),
+                pid,
+                cpu_percent,
+                rss_bytes: sample.rss_bytes,
+                threads: sample.threads,
+                children: sample.children,
+                alerts,
+                restart_pending,
+            };
+            if let Some(state) = app.try_state::<MetricsState>() {
+                state.push(metrics.clone());
+            }
+            let _ = app.emit(CORE_METRICS_EVENT, metrics);
+
+            // Restart between tasks only, never in the middle of an agent run
+            if restart_pending && tauri::async_runtime::block_on(kernel_is_idle(&app)) == Some(true) {
+                log::info!("Kernel is idle, restarting it to release memory");
+                let restart_app = app.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = crate::core_manager::restart_core(restart_app).await {
+                        log::error!("Memory threshold restart failed: {}", e);
+                    }
+                });
+                break;
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_core_metrics(state: tauri::State<'_, MetricsState>) -> Result<Vec<CoreMetrics>, String> {
+    Ok(state.history.lock().map(|h| h.iter().cloned().collect()).unwrap_or_default())
+}
@@ -0,0 +1,196 @@
+use std::io;
+use std::process::{Child, Command};
+
+// Handle on the kernel and everything it launched (Chromium, helpers).
+// On Unix the kernel leads its own process group, on Windows it is placed in a
+// Job Object that is killed when the handle closes.
+pub struct ProcessTree {
+    #[cfg(unix)]
+    pgid: u32,
+    #[cfg(windows)]
+    job: windows_job::Job,
+}
+
+// Must be applied to the command before spawning
+pub fn configure(cmd: &mut Command) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
+    #[cfg(not(unix))]
+    let _ = cmd;
+}
+
+impl ProcessTree {
+    pub fn attach(child: &Child) -> io::Result<Self> {
+        #[cfg(unix)]
+        {
+            Ok(Self { pgid: child.id() })
+        }
+        #[cfg(windows)]
+        {
+            let job = windows_job::Job::new()?;
+            job.assign(child)?;
+            Ok(Self { job })
+        }
+    }
+
+    // Ask the whole tree to exit. Windows has no console-less equivalent of
+    // SIGTERM, so there the HTTP shutdown request is the graceful step.
+    pub fn terminate(&self) -> io::Result<()> {
+        #[cfg(unix)]
+        {
+            signal_group(self.pgid, libc::SIGTERM)
+        }
+        #[cfg(windows)]
+        {
+            Ok(())
+        }
+    }
+
+    pub fn kill(&self) -> io::Result<()> {
+        #[cfg(unix)]
+        {
+            signal_group(self.pgid, libc::SIGKILL)
+        }
+        #[cfg(windows)]
+        {
+            self.job.terminate()
+        }
+    }
+}
+
+// Kill the process group of a kernel a previous shell launched and lost track of.
+// On Windows its Job Object already took it down when that shell's handle closed.
+pub fn kill_orphan(pid: u32) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        signal_group(pid, libc::SIGKILL)
+    }
+    #[cfg(windows)]
+    {
+        let _ = pid;
+        Ok(())
+    }
+}
+
+// Lower (or raise) the scheduling priority of the freshly spawned kernel. Processes
+// it launches later inherit it. Negative values usually need elevated rights.
+pub fn set_priority(child: &Child, nice: i32) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        let ret = unsafe { libc::setpriority(libc::PRIO_PROCESS, child.id() as libc::id_t, nice) };
+        if ret == 0 {
+            Ok(())
+        } else {
+            Err(io::Error::last_os_error())
+        }
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::io::AsRawHandle;
+        use windows_sys::Win32::System::Threading::{
+            SetPriorityClass, ABOVE_NORMAL_PRIORITY_CLASS, BELOW_NORMAL_PRIORITY_CLASS, HIGH_PRIORITY_CLASS,
+            IDLE_PRIORITY_CLASS, NORMAL_PRIORITY_CLASS,
+        };
+
+        let class = match nice {
+            15.. => IDLE_PRIORITY_CLASS,
+            5..=14 => BELOW_NORMAL_PRIORITY_CLASS,
+            -4..=4 => NORMAL_PRIORITY_CLASS,
+            -14..=-5 => ABOVE_NORMAL_PRIORITY_CLASS,
+            _ => HIGH_PRIORITY_CLASS,
+        };
+        let ok = unsafe { SetPriorityClass(child.as_raw_handle() as _, class) };
+        if ok == 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(unix)]
+fn signal_group(pgid: u32, signal: libc::c_int) -> io::Result<()> {
+    // A negative pid addresses the process group
+    let ret = unsafe { libc::kill(-(pgid as libc::pid_t), signal) };
+    if ret == 0 {
+        return Ok(());
+    }
+    let err = io::Error::last_os_error();
+    // ESRCH: the group is already gone
+    if err.raw_os_error() == Some(libc::ESRCH) {
+        Ok(())
+    } else {
+        Err(err)
+    }
+}
+
+#[cfg(windows)]
+mod windows_job {
+    use std::io;
+    use std::os::windows::io::AsRawHandle;
+    use std::process::Child;
+
+    use windows_sys::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows_sys::Win32::System::JobObjects::{
+        AssignProcessToJobObject, CreateJobObjectW, JobObjectExtendedLimitInformation, SetInformationJobObject,
+        TerminateJobObject, JOBOBJECT_EXTENDED_LIMIT_INFORMATION, JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+    };
+
+    pub struct Job(HANDLE);
+
+    // The handle is only used through thread-safe kernel32 calls
+    unsafe impl Send for Job {}
+    unsafe impl Sync for Job {}
+
+    impl Job {
+        pub fn new() -> io::Result<Self> {
+            let handle = unsafe { CreateJobObjectW(std::ptr::null(), std::ptr::null()) };
+            if handle.is_null() {
+                return Err(io::Error::last_os_error());
+            }
+            let job = Job(handle);
+
+            let mut info = JOBOBJECT_EXTENDED_LIMIT_INFORMATION::default();
+            info.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+            let ok = unsafe {
+                SetInformationJobObject(
+                    job.0,
+                    JobObjectExtendedLimitInformation,
+                    &info as *const _ as *const core::ffi::c_void,
+                    std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+                )
+            };
+            if ok == 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(job)
+        }
+
+        pub fn assign(&self, child: &Child) -> io::Result<()> {
+            let ok = unsafe { AssignProcessToJobObject(self.0, child.as_raw_handle() as HANDLE) };
+            if ok == 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        }
+
+        pub fn terminate(&self) -> io::Result<()> {
+            let ok = unsafe { TerminateJobObject(self.0, 1) };
+            if ok == 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        }
+    }
+
+    impl Drop for Job {
+        fn drop(&mut self) {
+            unsafe {
+                CloseHandle(self.0);
+            }
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Runtime};
+
+const PROFILE_FILE: &str = "launch-profile.json";
+
+const LOG_LEVELS: &[&str] = &["debug", "info", "warning", "error", "critical"];
+
+// Set by the shell itself and not overridable from the profile
+const RESERVED_ENV: &[&str] = &["YUHAI_CORE_HOST", "YUHAI_CORE_PORT", crate::core_api::CORE_TOKEN_ENV];
+
+// How the kernel is launched, persisted in app_config/launch-profile.json so the
+// kernel can be debugged without rebuilding the shell
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LaunchProfile {
+    // Appended after `start`
+    pub args: Vec<String>,
+    pub env: BTreeMap<String, String>,
+    // Passed as YUHAI_LOG_LEVEL
+    pub log_level: Option<String>,
+    // Passed as YUHAI_DATA_DIR
+    pub data_dir: Option<String>,
+    // Passed as PLAYWRIGHT_BROWSERS_PATH
+    pub playwright_browsers_path: Option<String>,
+    // Defaults to the kernel's directory
+    pub cwd: Option<String>,
+    // Unix nice value (-20..=19), mapped to a priority class on Windows
+    pub nice: Option<i32>,
+}
+
+fn absolute_dir(field: &str, value: &str, must_exist: bool) -> Result<PathBuf, String> {
+    let path = PathBuf::from(value);
+    if !path.is_absolute() {
+        return Err(format!("{} must be an absolute path: {}", field, value));
+    }
+    if path.exists() && !path.is_dir() {
+        return Err(format!("{} is not a directory: {}", field, value));
+    }
+    if must_exist && !path.is_dir() {
+        return Err(format!("{} does not exist: {}", field, value));
+    }
+    Ok(path)
+}
+
+impl LaunchProfile {
+    pub fn validate(&self) -> Result<(), String> {
+        for arg in &self.args {
+            if arg.is_empty() || arg.contains('\0') {
+                return Err(format!("Invalid kernel argument: {:?}", arg));
+            }
+        }
+        for (key, value) in &self.env {
+            if key.is_empty() || key.contains('=') || key.contains('\0') || value.contains('\0') {
+                return Err(format!("Invalid environment variable: {:?}", key));
+            }
+            if RESERVED_ENV.contains(&key.as_str()) {
+                return Err(format!("{} is managed by the app and can't be overridden", key));
+            }
+        }
+        if let Some(level) = &self.log_level {
+            if !LOG_LEVELS.contains(&level.to_lowercase().as_str()) {
+                return Err(format!("Unknown log level {}, expected one of {}", level, LOG_LEVELS.join(", ")));
+            }
+        }
+        if let Some(dir) = &self.data_dir {
+            absolute_dir("Data dir", dir, false)?;
+        }
+        if let Some(dir) = &self.playwright_browsers_path {
+            absolute_dir("Playwright browsers path", dir, true)?;
+        }
+        if let Some(dir) = &self.cwd {
+            absolute_dir("Working directory", dir, true)?;
+        }
+        if let Some(nice) = self.nice {
+            if !(-20..=19).contains(&nice) {
+                return Err(format!("Nice value must be between -20 and 19, got {}", nice));
+            }
+        }
+        Ok(())
+    }
+
+    // Apply args, environment and working directory to the `start` command
+    pub fn apply(&self, cmd: &mut Command, kernel_dir: &Path) {
+        cmd.args(&self.args);
+        cmd.envs(&self.env);
+        if let Some(level) = &self.log_level {
+            cmd.env("YUHAI_LOG_LEVEL", level.to_lowercase());
+        }
+        if let Some(dir) = &self.data_dir {
+            cmd.env("YUHAI_DATA_DIR", dir);
+        }
+        if let Some(dir) = &self.playwright_browsers_path {
+            cmd.env("PLAYWRIGHT_BROWSERS_PATH", dir);
+        }
+        match &self.cwd {
+            Some(dir) => cmd.current_dir(dir),
+            None => cmd.current_dir(kernel_dir),
+        };
+    }
+}
+
+fn profile_path<R: Runtime>(app: &AppHandle<R>) -> Option<PathBuf> {
+    app.path().app_config_dir().ok().map(|dir| dir.join(PROFILE_FILE))
+}
+
+pub fn load_profile<R: Runtime>(app: &AppHandle<R>) -> LaunchProfile {
+    profile_path(app)
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_profile<R: Runtime>(app: &AppHandle<R>, profile: &LaunchProfile) -> Result<(), String> {
+    let path = profile_path(app).ok_or("App config dir not available")?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let content = serde_json::to_string_pretty(profile).map_err(|e| e.to_string())?;
+    std::fs::write(&path, content).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_launch_profile<R: Runtime>(app: AppHandle<R>) -> Result<LaunchProfile, String> {
+    Ok(load_profile(&app))
+}
+
+// Takes effect on the next (re)start of the kernel
+#[tauri::command]
+pub fn set_launch_profile<R: Runtime>(app: AppHandle<R>, profile: LaunchProfile) -> Result<LaunchProfile, String> {
+    profile.validate()?;
+    save_profile(&app, &profile)?;
+    log::info!("Saved kernel launch profile: {:?}", profile);
+    Ok(profile)
+}
+
+#[tauri::command]
+pub fn reset_launch_profile<R: Runtime>(app: AppHandle<R>) -> Result<LaunchProfile, String> {
+    let profile = LaunchProfile::default();
+    save_profile(&app, &profile)?;
+    log::info!("Reset kernel launch profile");
+    Ok(profile)
+}
@@ -0,0 +1,292 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+fn get_core_ready_retry_count() -> u64 {
+    option_env!("VITE_CORE_READY_RETRY_COUNT")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(30)
+}
+
+fn get_core_ready_retry_interval_ms() -> u64 {
+    option_env!("VITE_CORE_READY_RETRY_INTERVAL_MS")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(2000)
+}
+
+fn get_core_ready_http_timeout_ms() -> u64 {
+    option_env!("VITE_CORE_READY_HTTP_TIMEOUT_MS")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(2000)
+}
+
+// Defaults to the old retry budget (count * interval) when not set explicitly
+fn get_core_ready_deadline_ms() -> u64 {
+    option_env!("VITE_CORE_READY_DEADLINE_MS")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(get_core_ready_retry_count() * get_core_ready_retry_interval_ms())
+}
+
+fn get_core_ready_probes() -> &'static str {
+    option_env!("VITE_CORE_READY_PROBES").unwrap_or("http")
+}
+
+fn get_core_ready_health_path() -> &'static str {
+    option_env!("VITE_CORE_READY_HEALTH_PATH").unwrap_or("/docs")
+}
+
+fn get_core_ready_expect_status() -> u16 {
+    option_env!("VITE_CORE_READY_EXPECT_STATUS")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(200)
+}
+
+fn get_core_ready_expect_body() -> Option<&'static str> {
+    option_env!("VITE_CORE_READY_EXPECT_BODY").filter(|v| !v.is_empty())
+}
+
+fn get_core_ready_marker() -> Option<&'static str> {
+    option_env!("VITE_CORE_READY_MARKER").filter(|v| !v.is_empty())
+}
+
+#[derive(Clone, Debug)]
+pub enum ReadinessProbe {
+    // GET `path` must answer with `expect_status` and, if set, contain `expect_body`
+    Http {
+        path: String,
+        expect_status: u16,
+        expect_body: Option<String>,
+    },
+    // The kernel port accepts TCP connections
+    Tcp,
+    // The kernel printed a line containing the marker
+    OutputMarker(MarkerWatch),
+}
+
+impl ReadinessProbe {
+    fn name(&self) -> String {
+        match self {
+            ReadinessProbe::Http { path, .. } => format!("http {}", path),
+            ReadinessProbe::Tcp => "tcp".to_string(),
+            ReadinessProbe::OutputMarker(watch) => format!("marker \"{}\"", watch.pattern),
+        }
+    }
+}
+
+// Shared with the kernel's pipe readers, flips once the marker has been printed.
+// Both stdout and stderr are observed since uvicorn logs its startup line to stderr.
+#[derive(Clone, Debug)]
+pub struct MarkerWatch {
+    pattern: Arc<str>,
+    seen: Arc<AtomicBool>,
+}
+
+impl MarkerWatch {
+    pub fn new(pattern: &str) -> Self {
+        Self {
+            pattern: Arc::from(pattern),
+            seen: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn observe(&self, line: &[u8]) {
+        if !self.seen.load(Ordering::Relaxed) && String::from_utf8_lossy(line).contains(&*self.pattern) {
+            self.seen.store(true, Ordering::Relaxed);
+        }
+    }
+
+    fn is_seen(&self) -> bool {
+        self.seen.load(Ordering::Relaxed)
+    }
+}
+
+pub struct ReadinessPlan {
+    pub probes: Vec<ReadinessProbe>,
+    pub deadline: Duration,
+    pub interval: Duration,
+    pub http_timeout: Duration,
+    // Shared secret sent with HTTP probes
+    pub token: Option<String>,
+}
+
+impl ReadinessPlan {
+    // Build the probe list from VITE_CORE_READY_PROBES, e.g. "http,tcp,marker"
+    pub fn from_env() -> Self {
+        let mut probes = Vec::new();
+        for name in get_core_ready_probes().split(',').map(|p| p.trim()) {
+            match name {
+                "http" => probes.push(ReadinessProbe::Http {
+                    path: get_core_ready_health_path().to_string(),
+                    expect_status: get_core_ready_expect_status(),
+                    expect_body: get_core_ready_expect_body().map(|b| b.to_string()),
+                }),
+                "tcp" => probes.push(ReadinessProbe::Tcp),
+                "marker" => match get_core_ready_marker() {
+                    Some(pattern) => probes.push(ReadinessProbe::OutputMarker(MarkerWatch::new(pattern))),
+                    None => log::warn!("Readiness probe 'marker' requested but VITE_CORE_READY_MARKER is not set"),
+                },
+                "" => {}
+                other => log::warn!("Unknown readiness probe '{}', ignoring", other),
+            }
+        }
+        if probes.is_empty() {
+            probes.push(ReadinessProbe::Tcp);
+        }
+
+        Self {
+            probes,
+            deadline: Duration::from_millis(get_core_ready_deadline_ms()),
+            interval: Duration::from_millis(get_core_ready_retry_interval_ms()),
+            http_timeout: Duration::from_millis(get_core_ready_http_timeout_ms()),
+            token: None,
+        }
+    }
+
+    // One HTTP round with no waiting, used to detect a kernel started elsewhere
+    pub fn single_check() -> Self {
+        Self {
+            probes: vec![ReadinessProbe::Http {
+                path: get_core_ready_health_path().to_string(),
+                expect_status: get_core_ready_expect_status(),
+                expect_body: get_core_ready_expect_body().map(|b| b.to_string()),
+            }],
+            deadline: Duration::ZERO,
+            interval: Duration::ZERO,
+            http_timeout: Duration::from_millis(get_core_ready_http_timeout_ms()),
+            token: None,
+        }
+    }
+
+    // A kernel that was already running printed its marker long ago, only the
+    // network probes can still tell anything about it
+    pub fn for_running_kernel(mut self) -> Self {
+        self.probes.retain(|p| !matches!(p, ReadinessProbe::OutputMarker(_)));
+        if self.probes.is_empty() {
+            self.probes = Self::single_check().probes;
+        }
+        self
+    }
+
+    pub fn marker_watch(&self) -> Option<MarkerWatch> {
+        self.probes.iter().find_map(|p| match p {
+            ReadinessProbe::OutputMarker(watch) => Some(watch.clone()),
+            _ => None,
+        })
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ProbeResult {
+    pub probe: String,
+    pub ok: bool,
+    pub detail: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadinessReport {
+    pub ready: bool,
+    pub elapsed_ms: u64,
+    pub attempts: u32,
+    pub probes: Vec<ProbeResult>,
+}
+
+impl ReadinessReport {
+    pub fn failure_summary(&self) -> String {
+        let failed: Vec<String> = self
+            .probes
+            .iter()
+            .filter(|p| !p.ok)
+            .map(|p| match &p.detail {
+                Some(detail) => format!("{} ({})", p.probe, detail),
+                None => p.probe.clone(),
+            })
+            .collect();
+        failed.join(", ")
+    }
+}
+
+async fn run_probe(client: &reqwest::Client, base: &str, probe: &ReadinessProbe, plan: &ReadinessPlan) -> ProbeResult {
+    let (ok, detail) = match probe {
+        ReadinessProbe::Http { path, expect_status, expect_body } => {
+            let mut request = client.get(format!("{}{}", base, path)).timeout(plan.http_timeout);
+            if let Some(token) = &plan.token {
+                request = request.header(crate::core_api::CORE_TOKEN_HEADER, token);
+            }
+            match request.send().await {
+                Ok(resp) if resp.status().as_u16() != *expect_status => {
+                    (false, Some(format!("status {}", resp.status().as_u16())))
+                }
+                Ok(resp) => match expect_body {
+                    Some(expected) => match resp.text().await {
+                        Ok(body) if body.contains(expected.as_str()) => (true, None),
+                        Ok(_) => (false, Some("unexpected body".to_string())),
+                        Err(e) => (false, Some(e.to_string())),
+                    },
+                    None => (true, None),
+                },
+                Err(e) => (false, Some(e.to_string())),
+            }
+        }
+        ReadinessProbe::Tcp => {
+            let addr = reqwest::Url::parse(base)
+                .ok()
+                .and_then(|url| Some(format!("{}:{}", url.host_str()?, url.port_or_known_default()?)));
+            match addr {
+                Some(addr) => match tokio::time::timeout(plan.http_timeout, tokio::net::TcpStream::connect(&addr)).await {
+                    Ok(Ok(_)) => (true, None),
+                    Ok(Err(e)) => (false, Some(e.to_string())),
+                    Err(_) => (false, Some("connect timed out".to_string())),
+                },
+                None => (false, Some(format!("invalid base url {}", base))),
+            }
+        }
+        ReadinessProbe::OutputMarker(watch) => {
+            if watch.is_seen() {
+                (true, None)
+            } else {
+                (false, Some("not printed yet".to_string()))
+            }
+        }
+    };
+    ProbeResult { probe: probe.name(), ok, detail }
+}
+
+// Run every probe until all pass in the same round, the deadline expires or
+// `alive` reports that the process is gone.
+pub async fn wait_until_ready(
+    client: &reqwest::Client,
+    base: &str,
+    plan: &ReadinessPlan,
+    alive: impl Fn() -> bool,
+) -> Result<ReadinessReport, ReadinessReport> {
+    let started = Instant::now();
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        let mut probes = Vec::with_capacity(plan.probes.len());
+        for probe in &plan.probes {
+            probes.push(run_probe(client, base, probe, plan).await);
+        }
+        let report = ReadinessReport {
+            ready: probes.iter().all(|p| p.ok),
+            elapsed_ms: started.elapsed().as_millis() as u64,
+            attempts,
+            probes,
+        };
+        if report.ready {
+            return Ok(report);
+        }
+        if !alive() {
+            return Err(report);
+        }
+        let elapsed = started.elapsed();
+        if elapsed >= plan.deadline {
+            return Err(report);
+        }
+        tokio::time::sleep(plan.interval.min(plan.deadline - elapsed)).await;
+    }
+}
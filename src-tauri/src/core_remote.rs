@@ -0,0 +1,365 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::core_api::CoreEndpoint;
+use crate::core_manager::{update_status, CoreMode, CorePhase, CoreState, CoreStatus};
+use crate::core_readiness::{wait_until_ready, ReadinessPlan, ReadinessReport};
+
+const REMOTE_FILE: &str = "remote-kernel.json";
+
+// Stands in for stored secrets in what the webview gets to see. Sending it back
+// unchanged keeps the stored value.
+const SECRET_MASK: &str = "********";
+
+// Set by the shell itself when proxying frontend requests
+const UNFORWARDED_HEADERS: &[&str] = &["authorization", "host", "content-length", "connection"];
+
+fn get_core_remote_poll_ms() -> u64 {
+    option_env!("VITE_CORE_REMOTE_POLL_MS")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(10000)
+}
+
+fn get_core_remote_timeout_ms() -> u64 {
+    option_env!("VITE_CORE_REMOTE_TIMEOUT_MS")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(15000)
+}
+
+// Bumped on every connect/disconnect so only the latest status poller keeps running
+static MONITOR_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+// Kernel running on another machine, persisted in app_config/remote-kernel.json
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RemoteProfile {
+    pub enabled: bool,
+    pub base_url: String,
+    // Shared secret the remote kernel was started with (YUHAI_CORE_TOKEN)
+    pub token: Option<String>,
+    // Basic auth for a reverse proxy in front of the kernel
+    pub username: Option<String>,
+    pub password: Option<String>,
+    // When set, only this certificate (PEM) is trusted instead of the system roots
+    pub pinned_cert_pem: Option<String>,
+}
+
+fn is_loopback(url: &reqwest::Url) -> bool {
+    match url.host_str() {
+        Some("localhost") => true,
+        Some(host) => host
+            .trim_matches(|c| c == '[' || c == ']')
+            .parse::<std::net::IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback()),
+        None => false,
+    }
+}
+
+fn mask(secret: &mut Option<String>) {
+    if secret.as_deref().is_some_and(|s| !s.is_empty()) {
+        *secret = Some(SECRET_MASK.to_string());
+    }
+}
+
+fn keep_if_masked(secret: &mut Option<String>, stored: &Option<String>) {
+    if secret.as_deref() == Some(SECRET_MASK) {
+        *secret = stored.clone();
+    }
+}
+
+impl RemoteProfile {
+    fn masked(mut self) -> Self {
+        mask(&mut self.token);
+        mask(&mut self.password);
+        self
+    }
+
+    // Restore secrets the webview only ever saw masked
+    fn merge_secrets(&mut self, stored: &RemoteProfile) {
+        keep_if_masked(&mut self.token, &stored.token);
+        keep_if_masked(&mut self.password, &stored.password);
+    }
+
+    fn parsed_url(&self) -> Result<reqwest::Url, String> {
+        let url = reqwest::Url::parse(self.base_url.trim()).map_err(|e| format!("Invalid kernel URL: {}", e))?;
+        match url.scheme() {
+            "https" => {}
+            // Plain HTTP would expose the token, only allowed through a local tunnel
+            "http" if is_loopback(&url) => {}
+            "http" => return Err("Remote kernels must be reached over https".to_string()),
+            other => return Err(format!("Unsupported kernel URL scheme: {}", other)),
+        }
+        Ok(url)
+    }
+
+    pub fn base(&self) -> Result<String, String> {
+        Ok(self.parsed_url()?.as_str().trim_end_matches('/').to_string())
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let url = self.parsed_url()?;
+        if self.password.is_some() && self.username.as_deref().is_none_or(|u| u.is_empty()) {
+            return Err("A password requires a username".to_string());
+        }
+        if let Some(pem) = &self.pinned_cert_pem {
+            if url.scheme() != "https" {
+                return Err("Certificate pinning requires an https URL".to_string());
+            }
+            reqwest::Certificate::from_pem(pem.as_bytes()).map_err(|e| format!("Invalid pinned certificate: {}", e))?;
+        }
+        Ok(())
+    }
+
+    pub fn build_client(&self) -> Result<reqwest::Client, String> {
+        let mut headers = reqwest::header::HeaderMap::new();
+        if let Some(username) = self.username.as_deref().filter(|u| !u.is_empty()) {
+            let credentials = format!("{}:{}", username, self.password.as_deref().unwrap_or_default());
+            let value = format!("Basic {}", base64::engine::general_purpose::STANDARD.encode(credentials));
+            let mut value = reqwest::header::HeaderValue::from_str(&value).map_err(|e| e.to_string())?;
+            value.set_sensitive(true);
+            headers.insert(reqwest::header::AUTHORIZATION, value);
+        }
+
+        let mut builder = reqwest::Client::builder()
+            .connect_timeout(Duration::from_secs(5))
+            .timeout(Duration::from_millis(get_core_remote_timeout_ms()))
+            .min_tls_version(reqwest::tls::Version::TLS_1_2)
+            .default_headers(headers);
+        if let Some(pem) = &self.pinned_cert_pem {
+            let cert = reqwest::Certificate::from_pem(pem.as_bytes()).map_err(|e| format!("Invalid pinned certificate: {}", e))?;
+            builder = builder.tls_built_in_root_certs(false).add_root_certificate(cert);
+        }
+        builder.build().map_err(|e| format!("Failed to build remote kernel client: {}", e))
+    }
+}
+
+fn profile_path<R: Runtime>(app: &AppHandle<R>) -> Option<std::path::PathBuf> {
+    app.path().app_config_dir().ok().map(|dir| dir.join(REMOTE_FILE))
+}
+
+pub fn load_profile<R: Runtime>(app: &AppHandle<R>) -> RemoteProfile {
+    profile_path(app)
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+// The profile holds the token and proxy password, so only the user may read it.
+// On Windows the per-user app config dir already has an owner-only ACL.
+fn save_profile<R: Runtime>(app: &AppHandle<R>, profile: &RemoteProfile) -> Result<(), String> {
+    use std::io::Write;
+
+    let path = profile_path(app).ok_or("App config dir not available")?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let content = serde_json::to_string_pretty(profile).map_err(|e| e.to_string())?;
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        options.mode(0o600);
+        // Files written before this went in
+        if path.exists() {
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).map_err(|e| e.to_string())?;
+        }
+    }
+    let mut file = options.open(&path).map_err(|e| e.to_string())?;
+    file.write_all(content.as_bytes()).map_err(|e| e.to_string())
+}
+
+pub fn is_remote<R: Runtime>(app: &AppHandle<R>) -> bool {
+    app.state::<CoreEndpoint>().is_remote()
+}
+
+async fn check_health<R: Runtime>(app: &AppHandle<R>) -> Result<ReadinessReport, ReadinessReport> {
+    let endpoint = app.state::<CoreEndpoint>();
+    let client = endpoint.client(&app.state::<reqwest::Client>());
+    let mut plan = ReadinessPlan::single_check();
+    plan.token = endpoint.token();
+    wait_until_ready(&client, &endpoint.base(), &plan, || false).await
+}
+
+// Switch the shell over to the remote kernel and report whether it answers
+pub async fn connect<R: Runtime>(app: &AppHandle<R>, profile: &RemoteProfile) -> Result<ReadinessReport, String> {
+    profile.validate()?;
+    let base = profile.base()?;
+    let client = profile.build_client()?;
+    app.state::<CoreEndpoint>().set_remote(base.clone(), profile.token.clone(), client);
+    update_status(app, |status| {
+        status.phase = CorePhase::Starting;
+        status.mode = CoreMode::Remote;
+        status.pid = None;
+        status.started_at = None;
+        status.kernel_path = None;
+        status.version = None;
+        status.last_error = None;
+    });
+    log::info!("Using remote kernel at {}", base);
+
+    let result = check_health(app).await;
+    apply_health(app, &result);
+    monitor(app.clone());
+    result.map_err(|report| format!("Remote kernel at {} is not reachable: {}", base, report.failure_summary()))
+}
+
+fn apply_health<R: Runtime>(app: &AppHandle<R>, result: &Result<ReadinessReport, ReadinessReport>) {
+    update_status(app, |status| match result {
+        Ok(_) => {
+            if status.phase != CorePhase::Ready {
+                status.started_at = Some(std::time::Instant::now());
+            }
+            status.phase = CorePhase::Ready;
+            status.last_error = None;
+        }
+        Err(report) => {
+            status.phase = CorePhase::Failed;
+            status.started_at = None;
+            status.last_error = Some(format!("Remote kernel is not reachable: {}", report.failure_summary()));
+        }
+    });
+}
+
+// Keep the status of the remote kernel current, it can't be watched as a process
+fn monitor<R: Runtime>(app: AppHandle<R>) {
+    let generation = MONITOR_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    tauri::async_runtime::spawn(async move {
+        let interval = Duration::from_millis(get_core_remote_poll_ms().max(1000));
+        loop {
+            tokio::time::sleep(interval).await;
+            if MONITOR_GENERATION.load(Ordering::SeqCst) != generation || !is_remote(&app) {
+                break;
+            }
+            let result = check_health(&app).await;
+            apply_health(&app, &result);
+        }
+    });
+}
+
+fn disconnect<R: Runtime>(app: &AppHandle<R>) {
+    MONITOR_GENERATION.fetch_add(1, Ordering::SeqCst);
+    app.state::<CoreEndpoint>().clear_remote();
+    update_status(app, |status| {
+        status.phase = CorePhase::Stopped;
+        status.mode = CoreMode::Owned;
+        status.started_at = None;
+        status.last_error = None;
+    });
+    log::info!("Left remote kernel mode");
+}
+
+fn current_status<R: Runtime>(app: &AppHandle<R>) -> Result<CoreStatus, String> {
+    crate::core_manager::get_core_status(app.state::<CoreState>())
+}
+
+// Secrets come back masked, see SECRET_MASK
+#[tauri::command]
+pub fn get_remote_profile<R: Runtime>(app: AppHandle<R>) -> Result<RemoteProfile, String> {
+    Ok(load_profile(&app).masked())
+}
+
+// Save the profile and switch modes: a local kernel is stopped before going
+// remote, and started again when remote mode is turned off
+#[tauri::command]
+pub async fn set_remote_profile<R: Runtime>(app: AppHandle<R>, mut profile: RemoteProfile) -> Result<CoreStatus, String> {
+    profile.merge_secrets(&load_profile(&app));
+    if profile.enabled || !profile.base_url.trim().is_empty() {
+        profile.validate()?;
+    }
+    save_profile(&app, &profile)?;
+
+    if profile.enabled {
+        let stop_app = app.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            let state = stop_app.state::<CoreState>();
+            crate::core_manager::shutdown_core_gracefully(&stop_app, &state)
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+        if let Err(e) = connect(&app, &profile).await {
+            log::warn!("{}", e);
+        }
+    } else if is_remote(&app) {
+        disconnect(&app);
+        if let Err(e) = crate::core_manager::launch_core(&app).await {
+            log::error!("Failed to start the local kernel: {}", e);
+        }
+    }
+    current_status(&app)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyRequest {
+    method: String,
+    // Path and query on the kernel, e.g. /api/v1/tasks/?page=1
+    path: String,
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+    // Base64, so uploads and other binary bodies survive the trip
+    #[serde(default)]
+    body: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyResponse {
+    status: u16,
+    headers: BTreeMap<String, String>,
+    // Base64 of the raw response, the frontend decodes it according to its responseType
+    body: String,
+}
+
+// Frontend requests to a remote kernel go through the shell, which holds the
+// proxy credentials, the pinned certificate and the token
+#[tauri::command]
+pub async fn core_proxy_request<R: Runtime>(app: AppHandle<R>, request: ProxyRequest) -> Result<ProxyResponse, String> {
+    if !is_remote(&app) {
+        return Err("Only requests to a remote kernel are proxied".to_string());
+    }
+    if !request.path.starts_with('/') {
+        return Err(format!("Invalid kernel path: {}", request.path));
+    }
+    let method = reqwest::Method::from_bytes(request.method.to_uppercase().as_bytes())
+        .map_err(|e| format!("Invalid method {}: {}", request.method, e))?;
+
+    let endpoint = app.state::<CoreEndpoint>();
+    let client = app.state::<reqwest::Client>();
+    let mut builder = endpoint.request(&client, method, &request.path);
+    for (name, value) in &request.headers {
+        let lower = name.to_ascii_lowercase();
+        if UNFORWARDED_HEADERS.contains(&lower.as_str()) || lower == crate::core_api::CORE_TOKEN_HEADER.to_ascii_lowercase() {
+            continue;
+        }
+        builder = builder.header(name, value);
+    }
+    if let Some(body) = request.body {
+        let body = base64::engine::general_purpose::STANDARD
+            .decode(body)
+            .map_err(|e| format!("Invalid request body: {}", e))?;
+        builder = builder.body(body);
+    }
+
+    let response = builder.send().await.map_err(|e| format!("Remote kernel request failed: {}", e))?;
+    let status = response.status().as_u16();
+    let headers = response
+        .headers()
+        .iter()
+        .filter_map(|(name, value)| value.to_str().ok().map(|v| (name.to_string(), v.to_string())))
+        .collect();
+    let body = response
+        .bytes()
+        .await
+        .map_err(|e| format!("Failed to read remote kernel response: {}", e))?;
+    Ok(ProxyResponse {
+        status,
+        headers,
+        body: base64::engine::general_purpose::STANDARD.encode(body),
+    })
+}
@@ -1,9 +1,10 @@
 use std::sync::{Arc, Mutex};
 
-use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use tauri::{AppHandle, Runtime, State};
 
+use crate::core_api::CoreEndpoint;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateInfo {
@@ -22,29 +23,7 @@ pub struct UpdateCheckResult {
 }
 
 fn get_current_platform() -> String {
-    option_env!("VITE_CORE_PLATFORM")
-        .unwrap_or({
-            let os = if cfg!(target_os = "windows") {
-                "win"
-            } else if cfg!(target_os = "linux") {
-                "linux"
-            } else if cfg!(target_os = "macos") {
-                "macos"
-            } else {
-                "unknown"
-            };
-            
-            let arch = if cfg!(target_arch = "x86_64") || cfg!(target_arch = "x86") {
-                "x64"
-            } else if cfg!(target_arch = "aarch64") || cfg!(target_arch = "arm64") {
-                "arm64"
-            } else {
-                "unknown"
-            };
-            
-            &format!("{}-{}", os, arch)
-        })
-        .to_string()
+    crate::core_install::current_platform()
 }
 
 #[derive(Deserialize, Debug)]
@@ -94,14 +73,13 @@ pub async fn core_update_check<R: Runtime>(
     _app: AppHandle<R>,
     state: State<'_, Arc<Mutex<UpdateState>>>,
     client: State<'_, reqwest::Client>,
+    endpoint: State<'_, CoreEndpoint>,
 ) -> Result<UpdateCheckResult, String> {
     let platform = get_current_platform();
     log::info!("Checking for core updates, current platform: {}", platform);
     
-    let check_url = format!("{}/api/v1/system/update/check?platform={}", crate::utils::core_api_base(), platform);
-
-    let resp = client
-        .get(&check_url)
+    let resp = endpoint
+        .get(&client, &format!("/api/v1/system/update/check?platform={}", platform))
         .send()
         .await
         .map_err(|e| format!("Failed to connect to core: {}", e))?
@@ -163,9 +141,10 @@ pub async fn core_update_check<R: Runtime>(
 
 #[tauri::command(rename_all = "snake_case")]
 pub async fn core_update_install<R: Runtime>(
-    _app: AppHandle<R>,
+    app: AppHandle<R>,
     state: State<'_, Arc<Mutex<UpdateState>>>,
     client: State<'_, reqwest::Client>,
+    endpoint: State<'_, CoreEndpoint>,
 ) -> Result<String, String> {
     let platform = get_current_platform();
     log::info!("Installing core update for platform: {}", platform);
@@ -177,10 +156,8 @@ pub async fn core_update_install<R: Runtime>(
 
     let download_url = info.download_url.ok_or("No download URL provided by update check")?;
 
-    let execute_url = format!("{}/api/v1/system/update/execute", crate::utils::core_api_base());
-
-    let resp = client
-        .post(&execute_url)
+    let resp = endpoint
+        .post(&client, "/api/v1/system/update/execute")
         .json(&serde_json::json!({
             "download_url": download_url,
             "version": info.version,
@@ -192,6 +169,12 @@ pub async fn core_update_install<R: Runtime>(
 
     if resp.status().is_success() {
         log::info!("Core update triggered successfully for version: {}", info.version);
+        // A remote kernel restarts on its own server, nothing to relaunch here
+        if endpoint.is_remote() {
+            crate::core_manager::set_phase(&app, crate::core_manager::CorePhase::Updating);
+        } else {
+            crate::core_manager::mark_update_pending(&app);
+        }
         Ok("Update triggered successfully. Service may restart.".to_string())
     } else {
         let error_msg = resp.text().await.unwrap_or_default();
@@ -219,11 +202,10 @@ pub fn core_update_cancel(_state: State<'_, Arc<Mutex<UpdateState>>>) -> Result<
 pub async fn core_update_get_version<R: Runtime>(
     _app: AppHandle<R>,
     client: State<'_, reqwest::Client>,
+    endpoint: State<'_, CoreEndpoint>,
 ) -> Result<String, String> {
     // We can try to fetch it from the check endpoint or just return "0.0.0" and let check handle it
-    let check_url = format!("{}/api/v1/system/update/check", crate::utils::core_api_base());
-
-    match client.get(&check_url).send().await {
+    match endpoint.get(&client, "/api/v1/system/update/check").send().await {
         Ok(resp) => {
              if let Ok(json) = resp.json::<ApiResponse<CoreUpdateResponse>>().await {
                  Ok(json.data.current_version)
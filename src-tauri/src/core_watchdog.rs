@@ -0,0 +1,145 @@
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+use crate::core_manager::{is_current_pid, update_status, CorePhase, CoreState};
+use crate::core_readiness::{wait_until_ready, ReadinessPlan};
+
+pub const CORE_WATCHDOG_EVENT: &str = "core:watchdog";
+
+fn get_core_watchdog_interval_ms() -> u64 {
+    option_env!("VITE_CORE_WATCHDOG_INTERVAL_MS")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(10000)
+}
+
+fn get_core_watchdog_failures() -> u32 {
+    option_env!("VITE_CORE_WATCHDOG_FAILURES")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(3)
+}
+
+fn get_core_watchdog_log_lines() -> usize {
+    option_env!("VITE_CORE_WATCHDOG_LOG_LINES")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(50)
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HangPolicy {
+    // Kill the tree and let the crash supervisor restart it with backoff
+    Restart,
+    // Only mark the kernel as hung
+    Notify,
+    Off,
+}
+
+fn get_core_watchdog_policy() -> HangPolicy {
+    match option_env!("VITE_CORE_WATCHDOG_POLICY").unwrap_or("restart") {
+        "notify" => HangPolicy::Notify,
+        "off" => HangPolicy::Off,
+        _ => HangPolicy::Restart,
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct HangReport {
+    pub pid: u32,
+    pub failures: u32,
+    pub detail: String,
+    pub policy: HangPolicy,
+    // Last kernel output before it stopped answering, oldest first
+    pub log_tail: Vec<String>,
+    pub ts: i64,
+}
+
+// Ping the kernel's health endpoint while `pid` is the current kernel
+pub fn start<R: Runtime>(app: AppHandle<R>, pid: u32) {
+    let policy = get_core_watchdog_policy();
+    if policy == HangPolicy::Off {
+        return;
+    }
+    tauri::async_runtime::spawn(async move {
+        let interval = Duration::from_millis(get_core_watchdog_interval_ms().max(1000));
+        let threshold = get_core_watchdog_failures().max(1);
+        let mut plan = ReadinessPlan::single_check();
+        plan.token = app.state::<crate::core_api::CoreEndpoint>().token();
+        let client = app.state::<reqwest::Client>().inner().clone();
+        let mut failures = 0;
+        let mut hung = false;
+
+        loop {
+            tokio::time::sleep(interval).await;
+            if !is_current_pid(&app, pid) {
+                break;
+            }
+            // Only a kernel that was answering before can hang
+            let phase = app.state::<CoreState>().status.lock().map(|s| s.phase).unwrap_or_default();
+            if phase != CorePhase::Ready && phase != CorePhase::Hung {
+                continue;
+            }
+
+            let base = app.state::<crate::core_api::CoreEndpoint>().base();
+            match wait_until_ready(&client, &base, &plan, || false).await {
+                Ok(_) => {
+                    if hung {
+                        log::info!("Kernel {} is responding again", pid);
+                        update_status(&app, |status| {
+                            status.phase = CorePhase::Ready;
+                            status.last_error = None;
+                        });
+                    }
+                    failures = 0;
+                    hung = false;
+                }
+                Err(report) => {
+                    failures += 1;
+                    log::warn!("Kernel health check failed ({}/{}): {}", failures, threshold, report.failure_summary());
+                    if failures < threshold || hung {
+                        continue;
+                    }
+                    hung = true;
+                    on_hang(&app, pid, failures, report.failure_summary(), policy);
+                    if policy == HangPolicy::Restart {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+}
+
+fn on_hang<R: Runtime>(app: &AppHandle<R>, pid: u32, failures: u32, detail: String, policy: HangPolicy) {
+    let log_tail = app
+        .try_state::<crate::core_log::CoreLogStore>()
+        .map(|store| store.tail(get_core_watchdog_log_lines()))
+        .unwrap_or_default();
+    log::error!(
+        "Kernel {} stopped responding after {} failed health checks: {}. Last output:\n{}",
+        pid,
+        failures,
+        detail,
+        log_tail.join("\n")
+    );
+
+    update_status(app, |status| {
+        status.phase = CorePhase::Hung;
+        status.last_error = Some(format!("Kernel stopped responding: {}", detail));
+    });
+    let _ = app.emit(CORE_WATCHDOG_EVENT, HangReport {
+        pid,
+        failures,
+        detail,
+        policy,
+        log_tail,
+        ts: chrono::Local::now().timestamp_millis(),
+    });
+
+    if policy == HangPolicy::Restart {
+        // A hung kernel won't honour the shutdown request, so don't wait for it
+        crate::core_manager::kill_hung_kernel(app, pid);
+    }
+}
@@ -3,11 +3,22 @@
 
 mod core_update;
 mod core_manager;
+mod core_log;
+mod core_api;
+mod core_readiness;
+mod core_process;
+mod core_inventory;
+mod core_discovery;
+mod core_integrity;
+mod core_profile;
+mod core_metrics;
+mod core_watchdog;
+mod core_remote;
+mod core_install;
 mod browser_sync;
 mod utils;
 
 use std::sync::{Arc, Mutex};
-use std::path::PathBuf;
 use chrono::Local;
 use tauri::Manager;
 
@@ -15,6 +26,7 @@ use tauri::Manager;
 async fn get_left_window_info(
     app: tauri::AppHandle,
     client: tauri::State<'_, reqwest::Client>,
+    endpoint: tauri::State<'_, core_api::CoreEndpoint>,
 ) -> Result<serde_json::Value, String> {
     let window = app.get_webview_window("main").ok_or("Main window not found")?;
     
@@ -63,11 +75,8 @@ async fn get_left_window_info(
         }
     });
 
-    // Query Kernel
-    let status_url = format!("{}/api/v1/browser/status", crate::utils::core_api_base());
-
     // Try to get browser status from kernel
-    match client.get(&status_url).send().await {
+    match endpoint.get(&client, "/api/v1/browser/status").send().await {
         Ok(res) => {
             if let Ok(json) = res.json::<serde_json::Value>().await {
                 // Merge kernel response into left_info
@@ -92,14 +101,6 @@ async fn get_left_window_info(
     Ok(left_info)
 }
 
-#[tauri::command]
-fn get_core_logs(_page: u32, _size: u32) -> Result<serde_json::Value, String> {
-     Ok(serde_json::json!({
-        "items": [],
-        "total": 0
-    }))
-}
-
 fn main() {
     let reqwest_client = reqwest::Client::builder()
         .no_proxy()
@@ -126,6 +127,10 @@ fn main() {
         .manage(reqwest_client)
         .manage(update_state)
         .manage(core_manager::CoreState::new())
+        .manage(core_api::CoreEndpoint::new())
+        .manage(core_metrics::MetricsState::new())
+        .manage(core_install::InstallState::new())
+        .manage(core_integrity::IntegrityCache::new())
         .setup(|app| {
             #[cfg(debug_assertions)]
             {
@@ -134,80 +139,65 @@ fn main() {
                 }
             }
             
+            // Persistent kernel log store
+            let log_dir = app.path().app_log_dir()
+                .map(|dir| dir.join("core"))
+                .unwrap_or_else(|_| std::env::temp_dir().join("yuHai-core-logs"));
+            app.manage(core_log::CoreLogStore::new(log_dir));
+
+            // Kernel manifest, refreshed in the background so setup never runs kernel binaries
+            app.manage(core_discovery::KernelDiscovery::load(app.handle()));
+
             // Initialize browser sync
             browser_sync::init(app.handle().clone());
 
-            // Extract bundled kernel
-            let handle = app.handle();
-            if let Ok(app_data_dir) = handle.path().app_data_dir() {
-                let core_dir = app_data_dir.join("core");
-                let kernel_zip_name = option_env!("VITE_CORE_ZIP_NAME")
-                    .unwrap_or("yuHai-core-win-x64.zip");
-                let kernel_zip_relative = PathBuf::from("resources").join(kernel_zip_name);
-                
-                log::info!("Platform-specific kernel zip name: {}", kernel_zip_name);
- 
-                if !core_dir.exists() {
-                    let _ = std::fs::create_dir_all(&core_dir);
-                }
- 
-                // Check if kernel exists using fast existence check (avoids running it to get version during setup)
-                let kernel_exists = core_manager::has_any_kernel(&handle);
-                
-                // Only extract if target executable does not exist
-                if !kernel_exists {
-                    #[cfg(debug_assertions)]
-                    // In development, the resource path is relative to the Cargo.toml location (src-tauri)
-                    let resource_path = kernel_zip_relative.clone();
-
-                    #[cfg(not(debug_assertions))]
-                    let resource_path = handle.path().resolve(kernel_zip_relative, tauri::path::BaseDirectory::Resource)
-                        .unwrap_or_else(|_| PathBuf::from(kernel_zip_name));
-
-                    // Try to resolve absolute path for better debugging
-                    let abs_resource_path = if resource_path.is_absolute() {
-                        resource_path.clone()
-                    } else {
-                        std::env::current_dir().unwrap_or_default().join(&resource_path)
-                    };
-
-                    if abs_resource_path.exists() {
-                        log::info!("Found bundled kernel zip at: {:?}", abs_resource_path);
-                        match std::fs::File::open(&abs_resource_path) {
-                            Ok(file) => {
-                                match zip::ZipArchive::new(file) {
-                                    Ok(mut archive) => {
-                                        if let Err(e) = archive.extract(&core_dir) {
-                                            log::error!("Failed to extract kernel zip: {}", e);
-                                        } else {
-                                            log::info!("Successfully extracted bundled kernel to: {:?}", core_dir);
-                                        }
-                                    },
-                                    Err(e) => log::error!("Failed to open zip archive: {}", e),
-                                }
-                            },
-                            Err(e) => log::error!("Failed to open zip file: {}", e),
-                        }
-                    } else {
-                         log::error!("Bundled kernel resource not found at: {:?} (resolved from {:?})", abs_resource_path, resource_path);
+            // A remote profile replaces the local kernel entirely
+            let remote = core_remote::load_profile(app.handle());
+            let remote_enabled = remote.enabled;
+            if remote_enabled {
+                let app_handle = app.handle().clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = core_remote::connect(&app_handle, &remote).await {
+                        log::error!("{}", e);
                     }
-                } else {
-                    log::info!("Kernel already exists, skipping extraction");
-                }
+                });
             }
 
-            // Start Core Kernel
-            #[cfg(not(debug_assertions))]
-            {
-                let app_handle = app.handle().clone();
-                let state = app_handle.state::<core_manager::CoreState>();
-                if let Err(e) = core_manager::start_core(app_handle.clone(), state) {
+            // Extract the bundled kernel on first run, or next to the installed ones when it
+            // is newer, off the setup thread so the window shows up right away. The kernel
+            // is started once that is done.
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                let install_app = app_handle.clone();
+                match tauri::async_runtime::spawn_blocking(move || core_install::install_or_upgrade_bundled(&install_app)).await {
+                    Ok(Err(e)) => log::error!("{}", e),
+                    Err(e) => log::error!("Kernel install task failed: {}", e),
+                    Ok(Ok(_)) => {}
+                }
+                // Kernels superseded while the app wasn't running to see it
+                core_manager::schedule_cleanup(&app_handle, "Startup");
+                if remote_enabled {
+                    return;
+                }
+
+                // Start Core Kernel
+                #[cfg(not(debug_assertions))]
+                if let Err(e) = core_manager::launch_core(&app_handle).await {
                     log::error!("Failed to auto-start core kernel: {}", e);
                 }
-            }
-            #[cfg(debug_assertions)]
-            log::info!("Skipping auto-start core kernel in dev environment");
-            
+                // In dev the kernel usually runs from source, pick it up if it is there
+                #[cfg(debug_assertions)]
+                {
+                    log::info!("Skipping auto-start core kernel in dev environment");
+                    let base = crate::utils::core_api_base().trim_end_matches('/').to_string();
+                    match core_manager::attach_external(&app_handle, base).await {
+                        Ok(_) => log::info!("Attached to dev kernel"),
+                        Err(e) => log::info!("No dev kernel to attach to: {}", e),
+                    }
+                }
+            });
+
+
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -218,16 +208,35 @@ fn main() {
             core_update::core_update_get_version,
             core_manager::start_core,
             core_manager::stop_core,
-            get_left_window_info,
-            get_core_logs
+            core_manager::restart_core,
+            core_manager::get_core_status,
+            core_manager::attach_core,
+            core_inventory::list_kernels,
+            core_inventory::select_kernel,
+            core_inventory::set_kernel_prerelease_policy,
+            core_manager::cleanup_kernels,
+            core_integrity::repair_kernel,
+            core_install::get_install_status,
+            core_install::download_kernel,
+            core_profile::get_launch_profile,
+            core_profile::set_launch_profile,
+            core_profile::reset_launch_profile,
+            core_metrics::get_core_metrics,
+            core_remote::get_remote_profile,
+            core_remote::set_remote_profile,
+            core_remote::core_proxy_request,
+            core_api::get_core_api_base,
+            core_api::get_core_auth,
+            core_log::get_core_logs,
+            get_left_window_info
         ])
         .build(tauri::generate_context!())
         .expect("error while building tauri application")
-        .run(|app_handle, event| match event {
-            tauri::RunEvent::ExitRequested { .. } => {
-                let state = app_handle.state::<core_manager::CoreState>();
-                core_manager::shutdown_core_gracefully(&state);
+        .run(|app_handle, event| {
+            if let tauri::RunEvent::ExitRequested { code, api, .. } = event {
+                if core_manager::shutdown_before_exit(app_handle, code) {
+                    api.prevent_exit();
+                }
             }
-            _ => {}
         });
 }
@@ -1,4 +1,5 @@
-import { coreRequest, CORE_BASE_URL } from '@/utils/http/core'
+import { reactive } from 'vue'
+import { coreRequest } from '@/utils/http/core'
 
 export interface NoteResource {
   id: number
@@ -172,6 +173,32 @@ export function addResources(note_id: string, files: File[]) {
   return coreRequest.post('/api/v1/note_detail_resource/resource/add', fd)
 }
 
+// 内核端口动态分配且请求需携带令牌，<img> 无法直接访问，
+// 因此经 coreRequest 以 blob 获取并缓存为 object URL，加载完成前返回空字符串
+const MAX_CACHED_IMAGES = 200
+const resourceImageUrls = reactive(new Map<string, string>())
+const pendingImages = new Set<string>()
+
 export function getNoteResourceImageUrl(resource_id: string | number) {
-  return `${CORE_BASE_URL}/api/v1/note_detail_resource/resource/image/${resource_id}`
+  const key = String(resource_id)
+  const cached = resourceImageUrls.get(key)
+  if (cached !== undefined) return cached
+  if (!pendingImages.has(key)) {
+    pendingImages.add(key)
+    coreRequest
+      .get<Blob>(`/api/v1/note_detail_resource/resource/image/${key}`, { responseType: 'blob' })
+      .then((blob) => {
+        if (resourceImageUrls.size >= MAX_CACHED_IMAGES) {
+          const [oldest, url] = resourceImageUrls.entries().next().value as [string, string]
+          URL.revokeObjectURL(url)
+          resourceImageUrls.delete(oldest)
+        }
+        resourceImageUrls.set(key, URL.createObjectURL(blob))
+      })
+      .catch(() => {
+        // 未缓存失败结果，下次渲染时重试
+      })
+      .finally(() => pendingImages.delete(key))
+  }
+  return ''
 }
@@ -1,4 +1,12 @@
-import axios, { AxiosInstance, AxiosRequestConfig, AxiosResponse } from 'axios'
+import axios, {
+  AxiosError,
+  AxiosHeaders,
+  AxiosInstance,
+  AxiosRequestConfig,
+  AxiosResponse,
+  InternalAxiosRequestConfig
+} from 'axios'
+import { invoke } from '@tauri-apps/api/core'
 import { useSettingStore } from '@/store/modules/setting'
 import { logger } from '@/utils/logger'
 
@@ -10,6 +18,121 @@ export const CORE_BASE_URL = import.meta.env.DEV
 
 logger.http.debug('Base URL:', CORE_BASE_URL)
 
+// 桌面端内核端口在启动时动态分配，由 Tauri 提供实际地址
+const resolveBaseURL = async (): Promise<string> => {
+  if (import.meta.env.DEV || !window.__TAURI_INTERNALS__) return CORE_BASE_URL
+  try {
+    return await invoke<string>('get_core_api_base')
+  } catch (error) {
+    logger.http.warn('Failed to resolve core base URL, using default:', error)
+    return CORE_BASE_URL
+  }
+}
+
+interface CoreAuth {
+  header: string
+  token: string | null
+  proxy: boolean
+}
+
+// 每次启动内核都会生成新的访问令牌，由 Tauri 提供，需随每个请求发送
+const resolveAuth = async (): Promise<CoreAuth | null> => {
+  if (!window.__TAURI_INTERNALS__) return null
+  try {
+    return await invoke<CoreAuth>('get_core_auth')
+  } catch (error) {
+    logger.http.warn('Failed to resolve core auth token:', error)
+    return null
+  }
+}
+
+interface ProxyResponse {
+  status: number
+  headers: Record<string, string>
+  // base64
+  body: string
+}
+
+const toBase64 = (buffer: ArrayBuffer): string => {
+  const bytes = new Uint8Array(buffer)
+  let binary = ''
+  for (let i = 0; i < bytes.length; i += 0x8000) {
+    binary += String.fromCharCode(...bytes.subarray(i, i + 0x8000))
+  }
+  return btoa(binary)
+}
+
+const fromBase64 = (encoded: string): Uint8Array => Uint8Array.from(atob(encoded), (c) => c.charCodeAt(0))
+
+// 请求体统一编码为字节，FormData 由浏览器生成 multipart 内容及 boundary
+const encodeBody = async (data: unknown, headers: Record<string, string>): Promise<string | null> => {
+  if (data == null) return null
+  const raw =
+    typeof data === 'string' ||
+    data instanceof FormData ||
+    data instanceof Blob ||
+    data instanceof URLSearchParams ||
+    data instanceof ArrayBuffer ||
+    ArrayBuffer.isView(data)
+  const packed = new Response((raw ? data : JSON.stringify(data)) as BodyInit)
+  if (data instanceof FormData) {
+    Object.keys(headers)
+      .filter((name) => name.toLowerCase() === 'content-type')
+      .forEach((name) => delete headers[name])
+    headers['Content-Type'] = packed.headers.get('content-type') || 'multipart/form-data'
+  }
+  return toBase64(await packed.arrayBuffer())
+}
+
+// 按 responseType 还原响应体，文本交由 axios 的 transformResponse 解析 JSON
+const decodeBody = (body: string, config: InternalAxiosRequestConfig, headers: Record<string, string>) => {
+  const bytes = fromBase64(body)
+  switch (config.responseType) {
+    case 'blob':
+      return new Blob([bytes], { type: headers['content-type'] || '' })
+    case 'arraybuffer':
+      return bytes.buffer
+    default:
+      return new TextDecoder().decode(bytes)
+  }
+}
+
+// 远程内核可能位于需要认证的反向代理之后或使用固定证书，这些只有 Tauri 端持有，
+// 因此请求经由 Tauri 转发，请求体与响应体以 base64 传递
+const shellAdapter = async (config: InternalAxiosRequestConfig): Promise<AxiosResponse> => {
+  const headers: Record<string, string> = {}
+  Object.entries(AxiosHeaders.from(config.headers).toJSON()).forEach(([name, value]) => {
+    if (typeof value === 'string') headers[name] = value
+  })
+  const body = await encodeBody(config.data, headers)
+  const res = await invoke<ProxyResponse>('core_proxy_request', {
+    request: {
+      method: config.method || 'get',
+      path: axios.getUri({ ...config, baseURL: '' }),
+      headers,
+      body
+    }
+  })
+  const response: AxiosResponse = {
+    data: decodeBody(res.body, config, res.headers),
+    status: res.status,
+    statusText: '',
+    headers: res.headers,
+    config,
+    request: null
+  }
+  if (config.validateStatus && !config.validateStatus(res.status)) {
+    throw new AxiosError(
+      `Request failed with status code ${res.status}`,
+      AxiosError.ERR_BAD_RESPONSE,
+      config,
+      null,
+      response
+    )
+  }
+  return response
+}
+
 // 创建 Axios 实例
 const service: AxiosInstance = axios.create({
   baseURL: CORE_BASE_URL,
@@ -19,7 +142,14 @@ const service: AxiosInstance = axios.create({
 
 // 请求拦截器
 service.interceptors.request.use(
-  (config) => {
+  async (config) => {
+    config.baseURL = await resolveBaseURL()
+    const auth = await resolveAuth()
+    if (auth?.proxy) {
+      config.adapter = shellAdapter
+    } else if (auth?.token) {
+      config.headers[auth.header] = auth.token
+    }
     // 可以在这里添加核心服务需要的特定 Headers，例如 API Key 等
     const settingStore = useSettingStore()
     if (settingStore.tenantScope) {
@@ -77,8 +207,8 @@ export const coreRequest = {
   // 暴露原始实例用于特殊需求
   instance: service,
 
-  // 暴露 Base URL
-  baseURL: CORE_BASE_URL
+  // 暴露 Base URL，桌面端内核端口在运行时分配，需异步获取
+  resolveBaseURL
 }
 
 export default coreRequest